@@ -1,49 +1,125 @@
 #![allow(unused_variables, dead_code, unused_imports)]
+use std::collections::HashMap;
 
 fn main() {
     let input = include_str!("../.inputs/input01.txt");
-    let data = parse_input(input);
+    let data = parse_input(input).unwrap();
     let part1 = part1(&data);
     dbg!(part1);
     let part2 = part2(&data);
     dbg!(part2);
+
+    // `--stats` describes both columns and how far apart they are under each pairing
+    if std::env::args().any(|arg| arg == "--stats") {
+        for (name, list) in [("left", &data.left), ("right", &data.right)] {
+            println!("{name}: median {:?}, mode {:?}", median(list), mode(list));
+        }
+        for pairing in [Pairing::Sorted, Pairing::InOrder, Pairing::Opposite] {
+            let distance = pairwise_distance(&data.left, &data.right, pairing);
+            println!("{pairing:?} distance: {distance}");
+        }
+    }
+}
+
+/// The two columns of location IDs
+struct Lists {
+    left: Vec<u32>,
+    right: Vec<u32>,
+}
+
+fn parse_input(input: &str) -> Result<Lists, &'static str> {
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        let mut ids = line.split_whitespace().map(str::parse::<u32>);
+        match (ids.next(), ids.next(), ids.next()) {
+            (Some(Ok(l)), Some(Ok(r)), None) => {
+                left.push(l);
+                right.push(r);
+            }
+            _ => return Err("Expected two location IDs per line"),
+        }
+    }
+    Ok(Lists { left, right })
+}
+
+/// How the two columns are matched up before measuring the distance between them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pairing {
+    /// Smallest with smallest, second smallest with second smallest, and so on
+    Sorted,
+    /// Line by line, as written
+    InOrder,
+    /// Smallest with largest
+    Opposite,
+}
+
+/// Total distance between paired-up IDs. If one column is longer, its extra IDs are ignored.
+fn pairwise_distance(left: &[u32], right: &[u32], pairing: Pairing) -> u64 {
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+    match pairing {
+        Pairing::Sorted => {
+            left.sort_unstable();
+            right.sort_unstable();
+        }
+        Pairing::InOrder => {}
+        Pairing::Opposite => {
+            left.sort_unstable();
+            right.sort_unstable_by(|a, b| b.cmp(a));
+        }
+    }
+    left.iter()
+        .zip(&right)
+        .map(|(&l, &r)| l.abs_diff(r) as u64)
+        .sum()
+}
+
+/// How many times each ID appears
+fn frequencies(list: &[u32]) -> HashMap<u32, usize> {
+    let mut counts = HashMap::new();
+    for &id in list {
+        *counts.entry(id).or_default() += 1;
+    }
+    counts
 }
 
-fn parse_input(input: &str) -> Vec<&str> {
-    input.lines().collect()
+/// Each left ID times the number of times it appears on the right, summed
+fn similarity(left: &[u32], right: &[u32]) -> u64 {
+    let counts = frequencies(right);
+    left.iter()
+        .map(|id| *id as u64 * counts.get(id).copied().unwrap_or(0) as u64)
+        .sum()
 }
 
-fn part1(input: &Vec<&str>) -> String {
-    let mut list_1 = Vec::new();
-    let mut list_2 = Vec::new();
-    input.iter().for_each(|s| {
-        let mut s = s.split_whitespace();
-        list_1.push(s.next().unwrap().parse::<u32>().unwrap());
-        list_2.push(s.next().unwrap().parse::<u32>().unwrap());
-    });
-    list_1.sort();
-    list_2.sort();
-    list_1
-        .iter()
-        .zip(list_2.iter())
-        .map(|(loc_1, loc_2)| u32::abs_diff(*loc_1, *loc_2))
-        .sum::<u32>()
-        .to_string()
+/// The middle ID, or the mean of the two middle ones for an even count
+fn median(list: &[u32]) -> Option<f64> {
+    if list.is_empty() {
+        return None;
+    }
+    let mut list = list.to_vec();
+    let (len, middle) = (list.len(), list.len() / 2);
+    // everything before the middle ends up no bigger than it, in linear time
+    let (below, &mut upper, _) = list.select_nth_unstable(middle);
+    if len % 2 == 1 {
+        return Some(upper as f64);
+    }
+    let lower = *below.iter().max().unwrap();
+    Some((lower as f64 + upper as f64) / 2.0)
 }
 
-fn part2(input: &Vec<&str>) -> String {
-    let mut list_1 = Vec::new();
-    let mut list_2 = Vec::new();
-    input.iter().for_each(|s| {
-        let mut s = s.split_whitespace();
-        list_1.push(s.next().unwrap().parse::<u32>().unwrap());
-        list_2.push(s.next().unwrap().parse::<u32>().unwrap());
-    });
-    list_1
-        .iter()
-        .map(|loc_1| loc_1 * list_2.iter().filter(|&loc_2| *loc_2 == *loc_1).count() as u32)
-        .sum::<u32>()
-        .to_string()
+/// The most common ID, the smallest one if there's a tie
+fn mode(list: &[u32]) -> Option<u32> {
+    frequencies(list)
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+        .map(|(id, _)| id)
+}
+
+fn part1(input: &Lists) -> String {
+    pairwise_distance(&input.left, &input.right, Pairing::Sorted).to_string()
+}
+
+fn part2(input: &Lists) -> String {
+    similarity(&input.left, &input.right).to_string()
 }
 
 #[cfg(test)]
@@ -59,11 +135,30 @@ mod tests {
         3   9
         3   3";
         let ex_answer = "11";
-        let ex_data = parse_input(ex_input);
+        let ex_data = parse_input(ex_input).unwrap();
         let result = part1(&ex_data);
         assert_eq!(result, ex_answer);
         let ex_answer2 = "31";
         let result2 = part2(&ex_data);
         assert_eq!(result2, ex_answer2);
     }
+
+    #[test]
+    fn test_statistics() {
+        let ex_data = parse_input("3 4\n4 3\n2 5\n1 3\n3 9\n3 3").unwrap();
+        let (left, right) = (&ex_data.left, &ex_data.right);
+        assert_eq!(median(left), Some(3.0));
+        assert_eq!(median(right), Some(3.5));
+        assert_eq!(median(&[]), None);
+        assert_eq!(mode(right), Some(3));
+        assert_eq!(mode(&[7, 2, 7, 2]), Some(2));
+
+        assert_eq!(pairwise_distance(left, right, Pairing::Sorted), 11);
+        assert_eq!(pairwise_distance(left, right, Pairing::InOrder), 13);
+        assert_eq!(pairwise_distance(left, right, Pairing::Opposite), 13);
+        assert_eq!(similarity(left, right), 31);
+
+        assert!(parse_input("1 2 3").is_err());
+        assert!(parse_input("1 x").is_err());
+    }
 }
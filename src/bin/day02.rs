@@ -21,63 +21,135 @@ fn parse_input(input: &str) -> Vec<Vec<u8>> {
     reports
 }
 
-// Checks if the vector is either all increasing or all decreasing, as well as making sure it is
-// "gradual" (each change is within the safety threshold)
-fn check_safe(report: &Vec<u8>) -> bool {
-    let safety_threshold = 3;
-    let increasing = report[1] > report[0];
-
-    for i in 1..report.len() {
-        let diff = u8::abs_diff(report[i], report[i - 1]);
-        if diff > safety_threshold || diff == 0 {
-            return false;
-        }
-        if (increasing && report[i] < report[i - 1]) || (!increasing && report[i] > report[i - 1]) {
-            return false;
+/// Which way the levels in a safe report may move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Trend {
+    Increasing,
+    Decreasing,
+    /// All increasing or all decreasing, whichever the report needs
+    Monotonic,
+    /// Each step may go either way
+    Any,
+}
+
+/// What makes a report safe: every step between adjacent levels is between `min_step` and
+/// `max_step` in size and goes the way `trend` allows, once up to `removals` levels have been
+/// dropped
+#[derive(Debug, Clone, Copy)]
+struct Validator {
+    min_step: u8,
+    max_step: u8,
+    trend: Trend,
+    removals: usize,
+}
+
+impl Validator {
+    const STRICT: Validator = Validator {
+        min_step: 1,
+        max_step: 3,
+        trend: Trend::Monotonic,
+        removals: 0,
+    };
+    /// The Problem Dampener tolerates one bad level
+    const DAMPENED: Validator = Validator {
+        removals: 1,
+        ..Validator::STRICT
+    };
+
+    fn is_safe(&self, report: &[u8]) -> bool {
+        self.check(report).is_some()
+    }
+
+    /// The fewest levels to remove to make the report safe, as indices in increasing order, or
+    /// `None` if it takes more than `removals`. A safe report needs no removals at all.
+    fn check(&self, report: &[u8]) -> Option<Vec<usize>> {
+        match self.trend {
+            Trend::Monotonic => {
+                let up = self.check_trend(report, Trend::Increasing);
+                let down = self.check_trend(report, Trend::Decreasing);
+                match (up, down) {
+                    (Some(up), Some(down)) if down.len() < up.len() => Some(down),
+                    (up, down) => up.or(down),
+                }
+            }
+            trend => self.check_trend(report, trend),
         }
     }
-    true
-}
 
-// One irregularity is allowed. So if we find a pair of unsafe levels, we try removing either one,
-// then check each of the edited reports for safety.
-fn check_safe_dampened(report: &Vec<u8>) -> bool {
-    let safety_threshold = 3;
-
-    for i in 1..report.len() {
-        let diff = u8::abs_diff(report[i], report[i - 1]);
-        if diff > safety_threshold || diff == 0 {
-            let mut edit_1 = report.clone();
-            edit_1.remove(i);
-            let mut edit_2 = report.clone();
-            edit_2.remove(i - 1);
-            return check_safe(&edit_1) || check_safe(&edit_2);
+    fn step_ok(&self, from: u8, to: u8, trend: Trend) -> bool {
+        let size = from.abs_diff(to);
+        let direction_ok = match trend {
+            Trend::Increasing => to > from,
+            Trend::Decreasing => to < from,
+            Trend::Monotonic | Trend::Any => true,
+        };
+        direction_ok && (self.min_step..=self.max_step).contains(&size)
+    }
+
+    /// Finds which levels to keep with a pass over (level, removals so far) states: from a
+    /// kept level, the next kept one is at most `removals` further on. That's O(n * k^2) for
+    /// k removals, so linear for any fixed k.
+    fn check_trend(&self, report: &[u8], trend: Trend) -> Option<Vec<usize>> {
+        let (n, k) = (report.len(), self.removals);
+        if n == 0 {
+            return Some(Vec::new());
         }
-        // very dumb way to instead check for increasing/decreasing allowing mismatches
-        let mut increasing_count = 0;
-        let mut decreasing_count = 0;
-
-        for j in 1..report.len() {
-            if report[j] > report[j - 1] {
-                increasing_count += 1;
-            } else if report[j] < report[j - 1] {
-                decreasing_count += 1;
+        let width = k + 1;
+        // for level i kept with r levels removed before it, the kept level before it, or
+        // `Some(None)` when it's the first one kept
+        let mut reached: Vec<Option<Option<usize>>> = vec![None; n * width];
+        for first in 0..=k.min(n - 1) {
+            reached[first * width + first] = Some(None);
+        }
+        for i in 0..n {
+            for removed in 0..=k {
+                if reached[i * width + removed].is_none() {
+                    continue;
+                }
+                for skipped in 0..=k - removed {
+                    let j = i + 1 + skipped;
+                    if j >= n {
+                        break;
+                    }
+                    let state = &mut reached[j * width + removed + skipped];
+                    if state.is_none() && self.step_ok(report[i], report[j], trend) {
+                        *state = Some(Some(i));
+                    }
+                }
             }
         }
-        let increasing = increasing_count >= decreasing_count;
-
-        if (increasing && report[i] < report[i - 1]) || (!increasing && report[i] > report[i - 1]) {
-            let mut edit_1 = report.clone();
-            edit_1.remove(i);
-            let mut edit_2 = report.clone();
-            edit_2.remove(i - 1);
-            return check_safe(&edit_1) || check_safe(&edit_2);
+
+        // everything after the last kept level has to go too
+        let (mut last, mut removed) = (0..n)
+            .flat_map(|last| (0..=k).map(move |removed| (last, removed)))
+            .filter(|&(last, removed)| {
+                reached[last * width + removed].is_some() && removed + (n - 1 - last) <= k
+            })
+            .min_by_key(|&(last, removed)| removed + (n - 1 - last))?;
+        let mut kept = vec![false; n];
+        loop {
+            kept[last] = true;
+            match reached[last * width + removed].unwrap() {
+                Some(previous) => {
+                    removed -= last - previous - 1;
+                    last = previous;
+                }
+                None => break,
+            }
         }
+        Some((0..n).filter(|&i| !kept[i]).collect())
     }
-    true
 }
 
-fn part1(input: &Vec<Vec<u8>>) -> String {
+fn check_safe(report: &[u8]) -> bool {
+    Validator::STRICT.is_safe(report)
+}
+
+fn check_safe_dampened(report: &[u8]) -> bool {
+    Validator::DAMPENED.is_safe(report)
+}
+
+fn part1(input: &[Vec<u8>]) -> String {
     input
         .iter()
         .filter(|report| check_safe(report))
@@ -85,7 +157,7 @@ fn part1(input: &Vec<Vec<u8>>) -> String {
         .to_string()
 }
 
-fn part2(input: &Vec<Vec<u8>>) -> String {
+fn part2(input: &[Vec<u8>]) -> String {
     input
         .iter()
         .filter(|report| check_safe_dampened(report))
@@ -116,8 +188,114 @@ mod tests {
     #[test]
     fn test_safety() {
         let safe_vec = vec![50, 51, 52, 54];
-        assert!(check_safe(&safe_vec) == true);
+        assert!(check_safe(&safe_vec));
         let damp_safe = vec![51, 50, 51, 52, 54];
-        assert!(check_safe_dampened(&damp_safe) == true);
+        assert!(check_safe_dampened(&damp_safe));
+    }
+
+    #[test]
+    fn test_removals() {
+        // dropping either the 3 or the 2 works
+        assert_eq!(Validator::DAMPENED.check(&[1, 3, 2, 4, 5]), Some(vec![2]));
+        assert_eq!(Validator::DAMPENED.check(&[8, 6, 4, 4, 1]), Some(vec![3]));
+        assert_eq!(Validator::DAMPENED.check(&[7, 6, 4, 2, 1]), Some(vec![]));
+        assert_eq!(Validator::DAMPENED.check(&[1, 2, 7, 8, 9]), None);
+        // the first or last level can be the bad one too
+        assert_eq!(Validator::DAMPENED.check(&[9, 1, 2, 3]), Some(vec![0]));
+        assert_eq!(Validator::DAMPENED.check(&[1, 2, 3, 9]), Some(vec![3]));
+
+        let two = Validator {
+            removals: 2,
+            ..Validator::STRICT
+        };
+        assert_eq!(two.check(&[1, 9, 2, 9, 3]), Some(vec![1, 3]));
+        let flat_ok = Validator {
+            min_step: 0,
+            max_step: 1,
+            trend: Trend::Any,
+            removals: 0,
+        };
+        assert!(flat_ok.is_safe(&[4, 4, 5, 4, 3, 3]));
+        assert!(!flat_ok.is_safe(&[4, 6]));
+    }
+
+    /// Tries every way of removing up to `removals` levels, checking each step directly
+    fn brute_force(validator: &Validator, report: &[u8]) -> Option<usize> {
+        let n = report.len();
+        let steps_ok = |levels: &[u8], trend: Trend| {
+            levels.windows(2).all(|pair| {
+                let size = pair[0].abs_diff(pair[1]);
+                let direction_ok = match trend {
+                    Trend::Increasing => pair[1] > pair[0],
+                    Trend::Decreasing => pair[1] < pair[0],
+                    _ => true,
+                };
+                direction_ok && size >= validator.min_step && size <= validator.max_step
+            })
+        };
+        let safe = |levels: &[u8]| match validator.trend {
+            Trend::Monotonic => {
+                steps_ok(levels, Trend::Increasing) || steps_ok(levels, Trend::Decreasing)
+            }
+            trend => steps_ok(levels, trend),
+        };
+        (0u32..1 << n)
+            .filter(|mask| mask.count_ones() as usize <= validator.removals)
+            .filter(|&mask| mask.count_ones() < n as u32 || n == 0)
+            .filter(|mask| {
+                let levels: Vec<u8> = (0..n)
+                    .filter(|i| mask >> i & 1 == 0)
+                    .map(|i| report[i])
+                    .collect();
+                safe(&levels)
+            })
+            .map(|mask| mask.count_ones() as usize)
+            .min()
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        let mut seed = 2024u64;
+        let mut next = |bound: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+        let trends = [
+            Trend::Increasing,
+            Trend::Decreasing,
+            Trend::Monotonic,
+            Trend::Any,
+        ];
+        for _ in 0..5000 {
+            let min_step = next(2) as u8;
+            let validator = Validator {
+                min_step,
+                max_step: min_step + next(4) as u8,
+                trend: trends[next(4) as usize],
+                removals: next(4) as usize,
+            };
+            let len = next(9) as usize;
+            let report: Vec<u8> = (0..len).map(|_| 1 + next(12) as u8).collect();
+
+            let found = validator.check(&report);
+            assert_eq!(
+                found.as_ref().map(Vec::len),
+                brute_force(&validator, &report),
+                "{validator:?} {report:?}"
+            );
+            if let Some(removed) = found {
+                let kept: Vec<u8> = (0..len)
+                    .filter(|i| !removed.contains(i))
+                    .map(|i| report[i])
+                    .collect();
+                let strict = Validator {
+                    removals: 0,
+                    ..validator
+                };
+                assert!(strict.is_safe(&kept), "{validator:?} {report:?}");
+            }
+        }
     }
 }
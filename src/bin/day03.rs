@@ -1,81 +1,164 @@
 #![allow(unused_variables, dead_code, unused_imports)]
+use aoc_2024::parse::{literal, number, preceded, scan, Cursor, Parser, Spanned};
+use std::fmt;
 
 fn main() {
     let input = include_str!("../.inputs/input03.txt");
-    let data = parse_input(input);
-    let part1 = part1(&data);
+    let part1 = part1(input);
     dbg!(part1);
     let part2 = part2(input);
     dbg!(part2);
+
+    // `--trace` lists every instruction found, with its byte offset and whether it ran
+    if std::env::args().any(|arg| arg == "--trace") {
+        Interpreter::new(true).run(input, |step, executed| {
+            let status = if executed { "run" } else { "skip" };
+            println!("{:>8}  {status:<4}  {}", step.offset, step.value);
+        });
+    }
 }
 
-// a regular expression would be nice here,,,
-fn parse_input(input: &str) -> Vec<(i32, i32)> {
-    let mut results = Vec::new();
-    let mut start = 0;
+/// State the instructions work on
+#[derive(Debug)]
+struct Machine {
+    enabled: bool,
+    total: i64,
+}
 
-    while let Some(pos) = input[start..].find("mul(") {
-        let actual_pos = start + pos;
-        start = actual_pos + 4;
+/// A decoded instruction and the arguments it was written with
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Instruction {
+    name: &'static str,
+    args: Vec<i64>,
+}
 
-        if let Some(end) = input[start..].find(')') {
-            let end_pos = start + end;
-            let content = &input[start..end_pos];
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args: Vec<_> = self.args.iter().map(|arg| arg.to_string()).collect();
+        write!(f, "{}({})", self.name, args.join(","))
+    }
+}
 
-            if let Some((x, y)) = parse_coords(content) {
-                results.push((x, y));
-                start = end_pos + 1;
-            }
-        } else {
-            break;
+type ArgParser<'a> = Box<dyn Fn(Cursor<'a>) -> Option<(Vec<i64>, Cursor<'a>)> + 'a>;
+
+/// One kind of instruction: how to pick it out of memory and what running it does
+struct Opcode<'a> {
+    name: &'static str,
+    parser: ArgParser<'a>,
+    execute: fn(&mut Machine, &[i64]),
+    /// Control instructions like `do()` still run while the machine is disabled
+    control: bool,
+}
+
+/// Decodes instructions from corrupted memory and runs them as it goes, in a single pass
+struct Interpreter<'a> {
+    opcodes: Vec<Opcode<'a>>,
+}
+
+impl<'a> Interpreter<'a> {
+    /// `mul(X,Y)` adds X * Y to the total. With `conditionals`, `do()` and `don't()` switch
+    /// the muls that follow on and off.
+    fn new(conditionals: bool) -> Self {
+        let interpreter = Interpreter {
+            opcodes: Vec::new(),
         }
+        .register(
+            "mul",
+            preceded(literal("mul("), number(1, 3))
+                .skip(literal(","))
+                .then(number(1, 3))
+                .skip(literal(")"))
+                .map(|(x, y)| vec![x, y]),
+            |machine, args| machine.total += args[0] * args[1],
+            false,
+        );
+        if !conditionals {
+            return interpreter;
+        }
+        interpreter
+            .register(
+                "do",
+                literal("do()").map(|_| Vec::new()),
+                |machine, _| machine.enabled = true,
+                true,
+            )
+            .register(
+                "don't",
+                literal("don't()").map(|_| Vec::new()),
+                |machine, _| machine.enabled = false,
+                true,
+            )
     }
 
-    results
-}
+    /// Teaches the interpreter a new instruction. `parser` reads the whole instruction and
+    /// returns its arguments. Opcodes are tried in the order they were registered.
+    fn register(
+        mut self,
+        name: &'static str,
+        parser: impl Parser<'a, Vec<i64>> + 'a,
+        execute: fn(&mut Machine, &[i64]),
+        control: bool,
+    ) -> Self {
+        self.opcodes.push(Opcode {
+            name,
+            parser: Box::new(move |input| parser.parse(input)),
+            execute,
+            control,
+        });
+        self
+    }
+
+    /// Every instruction in `input`, in order, skipping the garbage around them
+    fn instructions(
+        &self,
+        input: &'a str,
+    ) -> impl Iterator<Item = Spanned<(usize, Instruction)>> + use<'_, 'a> {
+        let decode = move |cursor: Cursor<'a>| {
+            self.opcodes.iter().enumerate().find_map(|(i, opcode)| {
+                let (args, rest) = (opcode.parser)(cursor)?;
+                let name = opcode.name;
+                Some(((i, Instruction { name, args }), rest))
+            })
+        };
+        scan(decode, input)
+    }
 
-fn parse_coords(content: &str) -> Option<(i32, i32)> {
-    let parts: Vec<&str> = content.split(',').collect();
-    if parts.len() == 2 {
-        if let (Ok(x), Ok(y)) = (parts[0].parse(), parts[1].parse()) {
-            return Some((x, y));
+    /// Runs every instruction in `input` on a fresh, enabled machine. `observe` sees each one
+    /// with whether it actually ran.
+    fn run(&self, input: &'a str, mut observe: impl FnMut(&Spanned<Instruction>, bool)) -> Machine {
+        let mut machine = Machine {
+            enabled: true,
+            total: 0,
+        };
+        for found in self.instructions(input) {
+            let (i, instruction) = found.value;
+            let opcode = &self.opcodes[i];
+            let executed = machine.enabled || opcode.control;
+            if executed {
+                (opcode.execute)(&mut machine, &instruction.args);
+            }
+            let step = Spanned {
+                value: instruction,
+                offset: found.offset,
+                len: found.len,
+            };
+            observe(&step, executed);
         }
+        machine
     }
-    None
 }
 
-fn part1(input: &Vec<(i32, i32)>) -> String {
-    input.iter().map(|(a, b)| a * b).sum::<i32>().to_string()
+fn part1(input: &str) -> String {
+    Interpreter::new(false)
+        .run(input, |_, _| {})
+        .total
+        .to_string()
 }
 
-// the plan will be to run through and remove the "dead" code in between the don't() and the next do(),
-// then repeat part 1
 fn part2(input: &str) -> String {
-    let mut current_code = input;
-    let mut executable = String::new();
-    let mut enabled = true;
-
-    loop {
-        if enabled {
-            let Some(stop_index) = current_code.find("don't()") else {
-                executable.push_str(current_code);
-                break;
-            };
-            executable.push_str(&current_code[..stop_index]);
-            current_code = &current_code[stop_index..];
-            enabled = false;
-        } else {
-            let Some(start_index) = current_code.find("do()") else {
-                break;
-            };
-            current_code = &current_code[start_index..];
-            enabled = true;
-        }
-    }
-    parse_input(&executable)
-        .iter()
-        .map(|(a, b)| a * b)
-        .sum::<i32>()
+    Interpreter::new(true)
+        .run(input, |_, _| {})
+        .total
         .to_string()
 }
 
@@ -87,11 +170,67 @@ mod tests {
     fn it_works() {
         let ex_input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
         let ex_answer = "161";
-        let ex_data = parse_input(ex_input);
-        let result = part1(&ex_data);
+        let result = part1(ex_input);
         assert_eq!(result, ex_answer);
         let ex_answer2 = "48";
         let result2 = part2(ex_input);
         assert_eq!(result2, ex_answer2);
     }
+
+    fn muls(input: &str) -> Vec<Vec<i64>> {
+        Interpreter::new(false)
+            .instructions(input)
+            .map(|found| found.value.1.args)
+            .collect()
+    }
+
+    #[test]
+    fn test_malformed() {
+        let corrupted = "mul( 2,4)mul(+2,4)mul(1234,5)mul(6,9!mul(2,4)mul(11,8)";
+        assert_eq!(muls(corrupted), vec![vec![2, 4], vec![11, 8]]);
+        assert_eq!(muls("mul(4*mul(3,7)"), vec![vec![3, 7]]);
+    }
+
+    #[test]
+    fn test_trace() {
+        let ex_input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let mut trace = Vec::new();
+        Interpreter::new(true).run(ex_input, |step, executed| {
+            trace.push(format!("{} {} {executed}", step.offset, step.value));
+        });
+        assert_eq!(
+            trace,
+            vec![
+                "1 mul(2,4) true",
+                "20 don't() true",
+                "28 mul(5,5) false",
+                "48 mul(11,8) false",
+                "59 do() true",
+                "64 mul(8,5) true",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_instruction() {
+        // `neg()` flips the sign of everything so far, and `add(X)` adds X
+        let interpreter = Interpreter::new(true)
+            .register(
+                "neg",
+                literal("neg()").map(|_| Vec::new()),
+                |machine, _| machine.total = -machine.total,
+                false,
+            )
+            .register(
+                "add",
+                preceded(literal("add("), number(1, 3))
+                    .skip(literal(")"))
+                    .map(|x| vec![x]),
+                |machine, args| machine.total += args[0],
+                false,
+            );
+        let machine = interpreter.run("mul(2,3)neg()add(10)don't()neg()add(5)", |_, _| {});
+        assert_eq!(machine.total, 4);
+        assert!(!machine.enabled);
+    }
 }
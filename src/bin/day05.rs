@@ -1,106 +1,226 @@
 #![allow(unused_variables, dead_code, unused_imports)]
-use std::collections::{HashMap, HashSet};
+use aoc_2024::graph::{Cycle, DiGraph};
+use std::fmt;
 
 fn main() {
     let input = include_str!("../.inputs/input05.txt");
     let data = parse_input(input);
-    let (rules, books) = parse_input(input);
-    let (part_1, part_2) = solution(rules, books);
-    dbg!(part_1);
-    dbg!(part_2);
+    let part1 = part1(&data);
+    dbg!(part1);
+    let part2 = part2(&data);
+    dbg!(part2);
+
+    // `--explain` lists the rules each misordered update breaks, next to its fixed order
+    if std::env::args().any(|arg| arg == "--explain") {
+        let graph = data.rules.to_graph();
+        for (i, book) in data.books.iter().enumerate() {
+            if let Some(diagnosis) = diagnose(&graph, book) {
+                println!("update {}: {diagnosis}", i + 1);
+            }
+        }
+    }
+}
+
+/// Page numbers are two digits, so every rule fits in a fixed-size matrix
+const PAGES: usize = 100;
+
+/// The ordering rules as a 100x100 bit matrix. Bit `b` of `successors[a]` is set for a rule
+/// `a|b`, and `predecessors` is the transpose, so checking a page against a whole set of
+/// pages is one AND.
+struct Rules {
+    successors: [u128; PAGES],
+    predecessors: [u128; PAGES],
+}
+
+impl Rules {
+    fn new() -> Self {
+        Rules {
+            successors: [0; PAGES],
+            predecessors: [0; PAGES],
+        }
+    }
+
+    fn add(&mut self, before: u8, after: u8) {
+        self.successors[before as usize] |= 1 << after;
+        self.predecessors[after as usize] |= 1 << before;
+    }
+
+    fn must_precede(&self, before: u8, after: u8) -> bool {
+        self.successors[before as usize] >> after & 1 == 1
+    }
+
+    /// Walks the update once, keeping the pages seen so far as a mask. A page is out of place
+    /// exactly when something it has to come before is already in the mask.
+    fn is_ordered(&self, book: &[u8]) -> bool {
+        let mut seen = 0u128;
+        for &page in book {
+            if self.successors[page as usize] & seen != 0 {
+                return false;
+            }
+            seen |= 1 << page;
+        }
+        true
+    }
+
+    /// Reorders the update to follow the rules by repeatedly taking the first page with no
+    /// unplaced predecessors. Returns `None` if the rules between its pages form a cycle.
+    fn sorted(&self, book: &[u8]) -> Option<Vec<u8>> {
+        let mut remaining = book.iter().fold(0u128, |mask, &page| mask | 1 << page);
+        let mut sorted = Vec::with_capacity(book.len());
+        while remaining != 0 {
+            let &page = book.iter().find(|&&page| {
+                remaining >> page & 1 == 1 && self.predecessors[page as usize] & remaining == 0
+            })?;
+            remaining &= !(1 << page);
+            sorted.push(page);
+        }
+        Some(sorted)
+    }
+
+    fn to_graph(&self) -> DiGraph<u8> {
+        let mut graph = DiGraph::new();
+        for before in 0..PAGES as u8 {
+            for after in 0..PAGES as u8 {
+                if self.must_precede(before, after) {
+                    graph.add_edge(before, after);
+                }
+            }
+        }
+        graph
+    }
+}
+
+struct Input {
+    rules: Rules,
+    books: Vec<Vec<u8>>,
 }
 
+/// Why an update is out of order, and what it should have been
 #[derive(Debug)]
-struct Page {
-    number: u8,
-    predecessors: HashSet<u8>,
+struct Diagnosis {
+    book: Vec<u8>,
+    /// Broken rules as position pairs `(i, j)`: the rule `book[i]|book[j]` is broken because
+    /// `j` comes first
+    violations: Vec<(usize, usize)>,
+    corrected: Result<Vec<u8>, Cycle<u8>>,
 }
 
-fn parse_input(input: &str) -> (HashMap<u8, Page>, Vec<Vec<u8>>) {
-    let mut book_rules: HashMap<u8, Page> = HashMap::new();
+fn diagnose(rules: &DiGraph<u8>, book: &[u8]) -> Option<Diagnosis> {
+    let violations: Vec<_> = rules
+        .violations(book)
+        .into_iter()
+        .map(|(i, j)| (j, i))
+        .collect();
+    if violations.is_empty() {
+        return None;
+    }
+    Some(Diagnosis {
+        book: book.to_vec(),
+        violations,
+        corrected: rules.subgraph(book).topological_sort(),
+    })
+}
+
+fn join(pages: &[u8]) -> String {
+    pages
+        .iter()
+        .map(|page| page.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", join(&self.book))?;
+        for &(i, j) in &self.violations {
+            let (before, after) = (self.book[i], self.book[j]);
+            writeln!(
+                f,
+                "  breaks {before}|{after}: {before} is at {i} but {after} is at {j}"
+            )?;
+        }
+        match &self.corrected {
+            Ok(corrected) => write!(f, "  fixed: {}", join(corrected)),
+            Err(cycle) => write!(f, "  no fix, the rules loop: {cycle}"),
+        }
+    }
+}
+
+fn parse_input(input: &str) -> Input {
+    let page = |num: &str| {
+        let page = num.parse::<u8>().unwrap();
+        assert!(
+            (page as usize) < PAGES,
+            "Page numbers must be below {PAGES}"
+        );
+        page
+    };
+    let mut rules = Rules::new();
     let (in_1, in_2) = input.split_once("\n\n").unwrap();
     in_1.lines().for_each(|line| {
         let (num_1, num_2) = line.split_once("|").unwrap();
-        let num_1 = num_1.parse::<u8>().unwrap();
-        let num_2 = num_2.parse::<u8>().unwrap();
-        if let Some(page) = book_rules.get_mut(&num_1) {
-            page.predecessors.insert(num_2);
-        } else {
-            book_rules.insert(
-                num_1,
-                Page {
-                    number: num_1,
-                    predecessors: HashSet::from([num_2]),
-                },
-            );
-        }
+        rules.add(page(num_1), page(num_2));
     });
-    let in_2 = in_2
+    let books = in_2
         .lines()
         .map(|line| {
-            line.split(',')
-                .map(|num| num.parse::<u8>().unwrap())
-                .collect::<Vec<_>>()
+            let book = line.split(',').map(page).collect::<Vec<_>>();
+            // pages are tracked as a bit mask, which can't count a page twice
+            let mut seen = 0u128;
+            for &page in &book {
+                assert!(
+                    seen >> page & 1 == 0,
+                    "Update {line} lists page {page} twice"
+                );
+                seen |= 1 << page;
+            }
+            book
         })
         .collect();
-    (book_rules, in_2)
+    Input { rules, books }
 }
 
-fn solution(rules: HashMap<u8, Page>, books: Vec<Vec<u8>>) -> (String, String) {
-    let (mut part_1, mut part_2) = (0, 0);
-
-    for book in books.iter() {
-        let mut is_sorted = true;
-        for (i, &page) in book.iter().enumerate() {
-            if let Some(page_rules) = rules.get(&page) {
-                for &predecessor in &page_rules.predecessors {
-                    if let Some(predecessor_index) = book.iter().position(|&p| p == predecessor) {
-                        if predecessor_index < i {
-                            is_sorted = false;
-                            break;
-                        }
-                    }
-                }
-            }
-            if !is_sorted {
-                break;
-            }
-        }
-
-        if is_sorted {
-            let middle_index = book.len() / 2;
-            part_1 += book[middle_index] as u32;
-        } else {
-            let mut sorted_book = book.clone();
-            sorted_book.sort_by(|&a, &b| {
-                if let Some(page_a) = rules.get(&a) {
-                    if page_a.predecessors.contains(&b) {
-                        return std::cmp::Ordering::Greater;
-                    }
-                }
-                if let Some(page_b) = rules.get(&b) {
-                    if page_b.predecessors.contains(&a) {
-                        return std::cmp::Ordering::Less;
-                    }
-                }
-                std::cmp::Ordering::Equal
-            });
-
-            let middle_index = sorted_book.len() / 2;
-            part_2 += sorted_book[middle_index] as u32;
-        }
-    }
+fn part1(input: &Input) -> String {
+    input
+        .books
+        .iter()
+        .filter(|book| input.rules.is_ordered(book))
+        .map(|book| book[book.len() / 2] as u32)
+        .sum::<u32>()
+        .to_string()
+}
 
-    (part_1.to_string(), part_2.to_string())
+/// The middle pages of the misordered updates once fixed, or the first loop in the rules that
+/// keeps an update from being fixed at all
+fn part2(input: &Input) -> String {
+    input
+        .books
+        .iter()
+        .filter(|book| !input.rules.is_ordered(book))
+        .try_fold(0u32, |total, book| {
+            // the full rule set loops back on itself, so only the rules between this book's
+            // pages can be sorted
+            match input.rules.sorted(book) {
+                Some(sorted) => Ok(total + sorted[sorted.len() / 2] as u32),
+                // sorting only fails on a loop, which the graph can name
+                None => Err(input
+                    .rules
+                    .to_graph()
+                    .subgraph(book)
+                    .topological_sort()
+                    .unwrap_err()),
+            }
+        })
+        .map_or_else(
+            |cycle| format!("unorderable, the rules loop: {cycle}"),
+            |total| total.to_string(),
+        )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    #[test]
-    fn it_works() {
-        let ex_input = "47|53
+    const EX_INPUT: &str = "47|53
 97|13
 97|61
 97|47
@@ -128,11 +248,72 @@ mod tests {
 75,97,47,61,53
 61,13,29
 97,13,75,29,47";
+
+    #[test]
+    fn test_part_1() {
         let ex_answer = "143";
-        let (rules, books) = parse_input(ex_input);
-        let (part_1, part_2) = solution(rules, books);
-        assert_eq!(part_1, ex_answer);
-        let ex_answer2 = "123";
-        assert_eq!(part_2, ex_answer2);
+        let ex_data = parse_input(EX_INPUT);
+        let result = part1(&ex_data);
+        assert_eq!(result, ex_answer);
+    }
+
+    #[test]
+    fn test_part_2() {
+        let ex_answer_2 = "123";
+        let ex_data = parse_input(EX_INPUT);
+        let result_2 = part2(&ex_data);
+        assert_eq!(result_2, ex_answer_2);
+    }
+
+    #[test]
+    fn test_rules() {
+        let ex_data = parse_input(EX_INPUT);
+        let graph = ex_data.rules.to_graph();
+        assert_eq!(graph.edge_count(), 21);
+        assert!(ex_data.rules.must_precede(97, 13) && !ex_data.rules.must_precede(13, 97));
+        for book in &ex_data.books {
+            assert_eq!(ex_data.rules.is_ordered(book), graph.is_consistent(book));
+        }
+        assert_eq!(
+            ex_data.rules.sorted(&ex_data.books[5]),
+            Some(vec![97, 75, 47, 29, 13])
+        );
+
+        let looped = parse_input("1|2\n2|3\n3|1\n99|0\n\n3,2,1\n0,99");
+        assert_eq!(looped.rules.sorted(&looped.books[0]), None);
+        assert_eq!(looped.rules.sorted(&looped.books[1]), Some(vec![99, 0]));
+        assert_eq!(
+            part2(&looped),
+            "unorderable, the rules loop: 1 -> 2 -> 3 -> 1"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Update 1,1,1,1,2 lists page 1 twice")]
+    fn test_repeated_page() {
+        parse_input("2|1\n\n1,1,1,1,2");
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let ex_data = parse_input(EX_INPUT);
+        let (rules, books) = (ex_data.rules.to_graph(), ex_data.books);
+        assert!(diagnose(&rules, &books[0]).is_none());
+
+        let diagnosis = diagnose(&rules, &books[3]).unwrap();
+        assert_eq!(
+            diagnosis.to_string(),
+            "75,97,47,61,53\n  breaks 97|75: 97 is at 1 but 75 is at 0\n  fixed: 97,75,47,61,53"
+        );
+        let diagnosis = diagnose(&rules, &books[5]).unwrap();
+        assert_eq!(diagnosis.violations.len(), 4);
+        assert_eq!(diagnosis.corrected, Ok(vec![97, 75, 47, 29, 13]));
+
+        // a rule set that contradicts itself can't be fixed, and says where
+        let looped = parse_input("1|2\n2|3\n3|1\n\n3,2,1");
+        let diagnosis = diagnose(&looped.rules.to_graph(), &looped.books[0]).unwrap();
+        assert!(diagnosis
+            .to_string()
+            .ends_with("no fix, the rules loop: 1 -> 2 -> 3 -> 1"));
     }
 }
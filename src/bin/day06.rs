@@ -1,5 +1,6 @@
 #![allow(unused_variables, dead_code, unused_imports)]
-use aoc_2024::grid::{Direction, Grid, GridObject};
+use aoc_2024::grid::{Animation, Color, Direction, Grid, GridObject, Overlay, Renderer, VisitSet};
+use std::time::Duration;
 
 fn main() {
     let input = include_str!("../.inputs/input06.txt");
@@ -8,6 +9,71 @@ fn main() {
     dbg!(part1);
     let part2 = part2(&data, starting_coords);
     dbg!(part2);
+
+    // `--animate` replays the guard's walk in the terminal, `--animate=frames.txt` writes it out
+    if let Some(arg) = std::env::args()
+        .nth(1)
+        .filter(|arg| arg.starts_with("--animate"))
+    {
+        let frames = animate(&data, starting_coords, arg.strip_prefix("--animate=")).unwrap();
+        dbg!(frames);
+    }
+    // `--image=guard.ppm` draws the guard's path and every loop-making obstacle position
+    if let Some(path) =
+        std::env::args().find_map(|arg| arg.strip_prefix("--image=").map(String::from))
+    {
+        draw(&data, starting_coords, &path).unwrap();
+    }
+}
+
+fn draw(grid: &Grid<GridObject>, coords: (usize, usize), path: &str) -> std::io::Result<()> {
+    let mut visited = VisitSet::new(grid.width, grid.height);
+    grid.run_guard(coords, None, &mut visited);
+    let overlays = [
+        Overlay::from_visits(&visited, Color::Yellow),
+        Overlay::new(grid.loop_obstacles(coords), Color::Red),
+        Overlay::new([coords], Color::Green),
+    ];
+    grid.write_ppm(
+        path,
+        4,
+        |cell| match cell {
+            GridObject::Obstacle => [60, 60, 60],
+            _ => [230, 230, 230],
+        },
+        &overlays,
+    )
+}
+
+fn animate(
+    grid: &Grid<GridObject>,
+    coords: (usize, usize),
+    file: Option<&str>,
+) -> std::io::Result<usize> {
+    let mut animation = match file {
+        Some(path) => Animation::to_file(path)?,
+        None => Animation::terminal(Duration::from_millis(30)),
+    };
+    let mut renderer = Renderer::new(|cell: &GridObject| match cell {
+        GridObject::Guy(_) => '.',
+        _ => cell.symbol(),
+    })
+    .ansi(file.is_none());
+    let mut visited = VisitSet::new(grid.width, grid.height);
+
+    for (position, direction) in grid.walk(coords, None) {
+        if !visited.insert(position, direction) {
+            break; // we are in a loop!
+        }
+        animation.step(|| {
+            renderer.set_overlays(vec![
+                Overlay::from_visits(&visited, Color::Yellow).with_glyph('X'),
+                Overlay::new([position], Color::Red).with_glyph(direction.symbol()),
+            ]);
+            renderer.render(grid)
+        })?;
+    }
+    animation.finish()
 }
 
 fn parse_input(input: &str) -> (Grid<GridObject>, (usize, usize)) {
@@ -17,7 +83,7 @@ fn parse_input(input: &str) -> (Grid<GridObject>, (usize, usize)) {
 }
 
 fn part1(input: &Grid<GridObject>, coords: (usize, usize)) -> String {
-    (input.run_grid(coords).unwrap() + 1).to_string() // adding 1 to account for the last space while leaving the warehouse
+    input.run_grid(coords).unwrap().to_string()
 }
 
 fn part2(input: &Grid<GridObject>, coords: (usize, usize)) -> String {
@@ -1,96 +1,514 @@
 #![allow(unused_variables, dead_code, unused_imports)]
+use std::fmt;
+use std::ops::{BitXor, ControlFlow, Div, Rem};
+use std::str::FromStr;
 
 fn main() {
     let input = include_str!("../.inputs/input07.txt");
-    let data = parse_input(input);
+    let data = parse_input(input).unwrap();
     let part1 = part1(&data);
     dbg!(part1);
     let part2 = part2(&data);
     dbg!(part2);
+
+    // `--explain` shows how each equation can be made true with the part 2 operators
+    if std::env::args().any(|arg| arg == "--explain") {
+        for calibration in &data {
+            println!("{}", calibration.explain());
+        }
+    }
 }
 
-#[derive(Debug)]
-struct Equation {
-    result: u64,
-    operands: Vec<u64>,
+/// An unsigned integer width the solver can work in. Every operation is checked, so running
+/// out of bits shows up as `None` rather than a panic or a wrapped value.
+trait Word:
+    Copy
+    + Ord
+    + fmt::Display
+    + FromStr
+    + From<u64>
+    + Into<u128>
+    + BitXor<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_pow(self, exp: u32) -> Option<Self>;
+    fn checked_ilog(self, base: Self) -> Option<u32>;
+    /// The value as an exponent. Anything past `u32::MAX` acts the same as `u32::MAX`, since
+    /// only 0 and 1 survive being raised that high.
+    fn saturating_u32(self) -> u32;
 }
-impl Equation {
-    fn is_valid(&self) -> bool {
-        Self::check_operators(self.result, &self.operands[..])
-    }
-    fn is_valid_concat(&self) -> bool {
-        Self::check_w_concat(self.result, &self.operands[..])
-    }
-    fn check_operators(result: u64, operands: &[u64]) -> bool {
-        if let Some((last, ops)) = operands.split_last() {
-            if ops.len() == 0 {
-                return result == *last;
+
+macro_rules! word {
+    ($($t:ty),*) => {$(
+        impl Word for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_add(self, rhs)
+            }
+            fn checked_sub(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_sub(self, rhs)
+            }
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_mul(self, rhs)
             }
-            if result % last == 0 && Self::check_operators(result / last, ops) {
-                return true;
+            fn checked_pow(self, exp: u32) -> Option<Self> {
+                <$t>::checked_pow(self, exp)
             }
-            if result >= *last && Self::check_operators(result - last, ops) {
-                return true;
+            fn checked_ilog(self, base: Self) -> Option<u32> {
+                <$t>::checked_ilog(self, base)
+            }
+            fn saturating_u32(self) -> u32 {
+                self.try_into().unwrap_or(u32::MAX)
             }
         }
-        false
+    )*};
+}
+
+word!(u64, u128);
+
+/// Why an operator has no value for its operands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    /// No natural number works, like `2 - 3`
+    Undefined,
+    /// There is a value but it needs more bits than the word has
+    Overflow,
+}
+
+/// A search that ran out of bits somewhere, so finding nothing doesn't mean there's nothing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Overflow;
+
+/// What the left operand has to be for `lhs op rhs == result`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Inverse<N> {
+    Never,
+    Exactly(N),
+    /// Every left operand works, like anything times zero
+    Any,
+    /// There is exactly one, but it's too big for the word
+    Overflow,
+}
+
+impl<N> From<Option<N>> for Inverse<N> {
+    fn from(lhs: Option<N>) -> Self {
+        lhs.map_or(Inverse::Never, Inverse::Exactly)
     }
-    fn check_w_concat(result: u64, operands: &[u64]) -> bool {
-        if let Some((last, ops)) = operands.split_last() {
-            if ops.is_empty() {
-                return result == *last;
-            }
-            // concat(a,b) = a*10^(# digits in b) + b
-            // # digits in b = log_{10}(b) + 1, or b.ilog10() + 1
-            let power = 10u64.pow(last.ilog10() + 1);
-            if result % power == *last && Self::check_w_concat(result / power, ops) {
-                return true;
+}
+
+/// A binary operator, evaluated strictly left to right. `apply` computes `lhs op rhs`, or
+/// says why it can't. `undo` goes the other way: given `result` and `rhs`, it works out which
+/// `lhs` gives `lhs op rhs == result`.
+#[derive(Debug, Clone, Copy)]
+struct Operator<N> {
+    symbol: &'static str,
+    apply: fn(N, N) -> Result<N, Failure>,
+    undo: fn(N, N) -> Inverse<N>,
+}
+
+impl<N: Word> Operator<N> {
+    const ADD: Self = Operator {
+        symbol: "+",
+        apply: |lhs, rhs| lhs.checked_add(rhs).ok_or(Failure::Overflow),
+        undo: |result, rhs| result.checked_sub(rhs).into(),
+    };
+    const MUL: Self = Operator {
+        symbol: "*",
+        apply: |lhs, rhs| lhs.checked_mul(rhs).ok_or(Failure::Overflow),
+        undo: |result, rhs| match (result == N::ZERO, rhs == N::ZERO) {
+            (true, true) => Inverse::Any,
+            (false, true) => Inverse::Never,
+            _ => (result % rhs == N::ZERO).then(|| result / rhs).into(),
+        },
+    };
+    const SUB: Self = Operator {
+        symbol: "-",
+        apply: |lhs, rhs| lhs.checked_sub(rhs).ok_or(Failure::Undefined),
+        undo: |result, rhs| {
+            result
+                .checked_add(rhs)
+                .map_or(Inverse::Overflow, Inverse::Exactly)
+        },
+    };
+    const XOR: Self = Operator {
+        symbol: "^",
+        apply: |lhs, rhs| Ok(lhs ^ rhs),
+        undo: |result, rhs| Inverse::Exactly(result ^ rhs),
+    };
+    /// `rhs` as the exponent
+    const POW: Self = Operator {
+        symbol: "**",
+        apply: |lhs, rhs| {
+            lhs.checked_pow(rhs.saturating_u32())
+                .ok_or(Failure::Overflow)
+        },
+        undo: |result, rhs| match rhs.saturating_u32() {
+            0 if result == N::ONE => Inverse::Any,
+            0 => Inverse::Never,
+            exp => integer_root(result, exp).into(),
+        },
+    };
+    const CONCAT: Self = Operator::concat::<10>("||");
+
+    /// Appends the digits of `rhs` to `lhs`, written in `BASE`
+    const fn concat<const BASE: u64>(symbol: &'static str) -> Self {
+        Operator {
+            symbol,
+            apply: |lhs, rhs| {
+                let shifted = match shift(rhs, BASE) {
+                    Some(shift) => lhs.checked_mul(shift).and_then(|lhs| lhs.checked_add(rhs)),
+                    // too many digits to shift anything but a zero past
+                    None => (lhs == N::ZERO).then_some(rhs),
+                };
+                shifted.ok_or(Failure::Overflow)
+            },
+            undo: |result, rhs| match shift(rhs, BASE) {
+                Some(shift) => (result % shift == rhs).then(|| result / shift).into(),
+                None => (result == rhs).then_some(N::ZERO).into(),
+            },
+        }
+    }
+}
+
+/// `base` raised to the number of digits in `n`, i.e. how far concatenating `n` shifts the
+/// left operand. Zero is written with one digit.
+fn shift<N: Word>(n: N, base: u64) -> Option<N> {
+    let base = N::from(base);
+    base.checked_pow(n.checked_ilog(base).unwrap_or(0) + 1)
+}
+
+/// The `root` with `root ** exp == n`, if there is a whole one
+fn integer_root<N: Word>(n: N, exp: u32) -> Option<N> {
+    let two = N::from(2);
+    let (mut low, mut high) = (N::ZERO, n);
+    while low <= high {
+        let mid = low.checked_add(high.checked_sub(low)? / two)?;
+        match mid.checked_pow(exp) {
+            Some(power) if power == n => return Some(mid),
+            Some(power) if power < n => low = mid.checked_add(N::ONE)?,
+            _ => high = mid.checked_sub(N::ONE)?,
+        }
+    }
+    None
+}
+
+#[derive(Debug)]
+struct Equation<N> {
+    result: N,
+    operands: Vec<N>,
+}
+
+impl<N: Word> Equation<N> {
+    fn parse(line: &str) -> Result<Self, &'static str> {
+        let (result, operands) = line.split_once(':').ok_or("Expected `result: operands`")?;
+        let number = |s: &str| s.trim().parse::<N>().map_err(|_| "Number doesn't fit");
+        Ok(Equation {
+            result: number(result)?,
+            operands: operands
+                .split_ascii_whitespace()
+                .map(number)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn is_valid(&self) -> Result<bool, Overflow> {
+        self.is_solvable(&[Operator::ADD, Operator::MUL])
+    }
+
+    fn is_valid_concat(&self) -> Result<bool, Overflow> {
+        self.is_solvable(&[Operator::ADD, Operator::MUL, Operator::CONCAT])
+    }
+
+    fn is_solvable(&self, operators: &[Operator<N>]) -> Result<bool, Overflow> {
+        self.witness(operators).map(|witness| witness.is_some())
+    }
+
+    /// The first operator sequence found that makes the equation true. Finding none is only
+    /// conclusive if nothing overflowed along the way.
+    fn witness<'a>(
+        &self,
+        operators: &'a [Operator<N>],
+    ) -> Result<Option<Vec<&'a Operator<N>>>, Overflow> {
+        match self.walk(operators, |ops| ControlFlow::Break(ops.to_vec())) {
+            (ControlFlow::Break(witness), _) => Ok(Some(witness)),
+            (ControlFlow::Continue(()), false) => Ok(None),
+            (ControlFlow::Continue(()), true) => Err(Overflow),
+        }
+    }
+
+    fn count_solutions(&self, operators: &[Operator<N>]) -> Result<usize, Overflow> {
+        Ok(self.solutions(operators)?.len())
+    }
+
+    /// Writes the equation out with the given operators filled in, e.g. `156 = 15 || 6`
+    fn expression(&self, operators: &[&Operator<N>]) -> String {
+        let mut expression = format!("{} =", self.result);
+        for (i, operand) in self.operands.iter().enumerate() {
+            if i > 0 {
+                expression += &format!(" {}", operators[i - 1].symbol);
             }
+            expression += &format!(" {operand}");
+        }
+        expression
+    }
 
-            if result % last == 0 && Self::check_w_concat(result / last, ops) {
-                return true;
+    /// The equation with a witness and a count of all solutions, or "unsatisfiable"
+    fn explain(&self, operators: &[Operator<N>]) -> Result<String, Overflow> {
+        let explained = match self.witness(operators)? {
+            Some(witness) => {
+                let count = self.count_solutions(operators)?;
+                let noun = if count == 1 { "solution" } else { "solutions" };
+                format!("{} ({count} {noun})", self.expression(&witness))
             }
-            if result >= *last && Self::check_w_concat(result - last, ops) {
-                return true;
+            None => format!("{self}: unsatisfiable"),
+        };
+        Ok(explained)
+    }
+
+    fn explain_concat(&self) -> Result<String, Overflow> {
+        self.explain(&[Operator::ADD, Operator::MUL, Operator::CONCAT])
+    }
+
+    /// Every sequence of operators that, placed between the operands, makes them evaluate to
+    /// the result. Each sequence has one operator per gap, in order.
+    fn solutions<'a>(
+        &self,
+        operators: &'a [Operator<N>],
+    ) -> Result<Vec<Vec<&'a Operator<N>>>, Overflow> {
+        let mut found = Vec::new();
+        let (_, overflowed) = self.walk(operators, |ops| {
+            found.push(ops.to_vec());
+            ControlFlow::<()>::Continue(())
+        });
+        if overflowed {
+            return Err(Overflow);
+        }
+        Ok(found)
+    }
+
+    /// Runs `search` over the whole equation, also returning whether any branch overflowed
+    fn walk<'a, B>(
+        &self,
+        operators: &'a [Operator<N>],
+        mut visit: impl FnMut(&[&'a Operator<N>]) -> ControlFlow<B>,
+    ) -> (ControlFlow<B>, bool) {
+        let (mut chosen, mut overflowed) = (Vec::new(), false);
+        let flow = Self::search(
+            self.result,
+            &self.operands,
+            operators,
+            &mut chosen,
+            &mut overflowed,
+            &mut visit,
+        );
+        (flow, overflowed)
+    }
+
+    /// Works backwards from the result, peeling off the last operand with each operator's
+    /// `undo`. `chosen` holds the operators picked so far, last gap first, and `visit` sees
+    /// each complete sequence in evaluation order. Branches that run out of bits are dropped
+    /// and set `overflowed`.
+    fn search<'a, B>(
+        result: N,
+        operands: &[N],
+        operators: &'a [Operator<N>],
+        chosen: &mut Vec<&'a Operator<N>>,
+        overflowed: &mut bool,
+        visit: &mut impl FnMut(&[&'a Operator<N>]) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let Some((&last, rest)) = operands.split_last() else {
+            return ControlFlow::Continue(());
+        };
+        if rest.is_empty() {
+            if result == last {
+                chosen.reverse();
+                let flow = visit(chosen);
+                chosen.reverse();
+                return flow;
             }
+            return ControlFlow::Continue(());
         }
+        for operator in operators {
+            chosen.push(operator);
+            let flow = match (operator.undo)(result, last) {
+                Inverse::Never => ControlFlow::Continue(()),
+                Inverse::Overflow => {
+                    *overflowed = true;
+                    ControlFlow::Continue(())
+                }
+                Inverse::Exactly(lhs) => {
+                    Self::search(lhs, rest, operators, chosen, overflowed, visit)
+                }
+                // whatever the operands before this one come to, the result is the same, so
+                // every way of evaluating them is a solution
+                Inverse::Any => {
+                    let tail: Vec<_> = chosen.iter().rev().copied().collect();
+                    let mut prefix = Vec::new();
+                    Self::each_evaluation(
+                        rest[0],
+                        &rest[1..],
+                        operators,
+                        &mut prefix,
+                        overflowed,
+                        &mut |ops| {
+                            let ops: Vec<_> = ops.iter().chain(&tail).copied().collect();
+                            visit(&ops)
+                        },
+                    )
+                }
+            };
+            chosen.pop();
+            flow?;
+        }
+        ControlFlow::Continue(())
+    }
 
-        false
+    /// Runs forwards from `acc` through every operator sequence over `operands` that evaluates,
+    /// setting `overflowed` if any of them can't be evaluated in this width
+    fn each_evaluation<'a, B>(
+        acc: N,
+        operands: &[N],
+        operators: &'a [Operator<N>],
+        chosen: &mut Vec<&'a Operator<N>>,
+        overflowed: &mut bool,
+        visit: &mut impl FnMut(&[&'a Operator<N>]) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let Some((&next, rest)) = operands.split_first() else {
+            return visit(chosen);
+        };
+        for operator in operators {
+            let acc = match (operator.apply)(acc, next) {
+                Ok(acc) => acc,
+                Err(failure) => {
+                    *overflowed |= failure == Failure::Overflow;
+                    continue;
+                }
+            };
+            chosen.push(operator);
+            let flow = Self::each_evaluation(acc, rest, operators, chosen, overflowed, visit);
+            chosen.pop();
+            flow?;
+        }
+        ControlFlow::Continue(())
     }
 }
 
-fn parse_input(input: &str) -> Vec<Equation> {
-    input
-        .lines()
-        .map(|line| {
-            let (res, ops) = line.split_once(":").unwrap();
-            Equation {
-                result: res.parse::<u64>().unwrap(),
-                operands: ops
-                    .split_ascii_whitespace()
-                    .map(|num| num.parse::<u64>().unwrap())
-                    .collect(),
+impl<N: Word> fmt::Display for Equation<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.result)?;
+        for operand in &self.operands {
+            write!(f, " {operand}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Equation<u64> {
+    /// The same equation in 128 bits, for when solving it in 64 overflows
+    fn widen(&self) -> Equation<u128> {
+        Equation {
+            result: self.result.into(),
+            operands: self.operands.iter().map(|&n| n.into()).collect(),
+        }
+    }
+}
+
+/// Evaluates the operands left to right with one operator per gap
+fn evaluate<N: Word>(operands: &[N], operators: &[&Operator<N>]) -> Option<N> {
+    let (&first, rest) = operands.split_first()?;
+    rest.iter()
+        .zip(operators)
+        .try_fold(first, |acc, (&rhs, operator)| {
+            (operator.apply)(acc, rhs).ok()
+        })
+}
+
+/// An equation solved in `u64` when everything on the line fits, and in `u128` when it doesn't.
+/// A narrow equation whose search overflows is solved again wide.
+#[derive(Debug)]
+enum Calibration {
+    Narrow(Equation<u64>),
+    Wide(Equation<u128>),
+}
+
+impl Calibration {
+    fn parse(line: &str) -> Result<Self, &'static str> {
+        Equation::parse(line)
+            .map(Calibration::Narrow)
+            .or_else(|_| Equation::parse(line).map(Calibration::Wide))
+            .map_err(|_| "Expected `result: operands` with numbers that fit in 128 bits")
+    }
+
+    fn result(&self) -> u128 {
+        match self {
+            Calibration::Narrow(equation) => equation.result.into(),
+            Calibration::Wide(equation) => equation.result,
+        }
+    }
+
+    fn is_valid(&self) -> Result<bool, Overflow> {
+        match self {
+            Calibration::Narrow(equation) => {
+                equation.is_valid().or_else(|_| equation.widen().is_valid())
             }
+            Calibration::Wide(equation) => equation.is_valid(),
+        }
+    }
+
+    fn is_valid_concat(&self) -> Result<bool, Overflow> {
+        match self {
+            Calibration::Narrow(equation) => equation
+                .is_valid_concat()
+                .or_else(|_| equation.widen().is_valid_concat()),
+            Calibration::Wide(equation) => equation.is_valid_concat(),
+        }
+    }
+
+    /// Explains the equation in terms of the part 2 operators
+    fn explain(&self) -> String {
+        let explained = match self {
+            Calibration::Narrow(equation) => equation
+                .explain_concat()
+                .or_else(|_| equation.widen().explain_concat()),
+            Calibration::Wide(equation) => equation.explain_concat(),
+        };
+        explained.unwrap_or_else(|_| match self {
+            Calibration::Narrow(equation) => format!("{equation}: overflows 128 bits"),
+            Calibration::Wide(equation) => format!("{equation}: overflows 128 bits"),
         })
-        .collect()
+    }
 }
 
-fn part1(input: &Vec<Equation>) -> String {
-    input
-        .iter()
-        .filter(|eq| eq.is_valid())
-        .map(|eq| eq.result)
-        .sum::<u64>()
-        .to_string()
+fn parse_input(input: &str) -> Result<Vec<Calibration>, &'static str> {
+    input.lines().map(Calibration::parse).collect()
 }
 
-fn part2(input: &Vec<Equation>) -> String {
+/// Sums the results of the calibrations that pass `check`, or gives "overflow" if the sum
+/// doesn't fit or some calibration can't be decided in 128 bits
+fn total(input: &[Calibration], check: impl Fn(&Calibration) -> Result<bool, Overflow>) -> String {
     input
         .iter()
-        .filter(|eq| eq.is_valid_concat())
-        .map(|eq| eq.result)
-        .sum::<u64>()
-        .to_string()
+        .try_fold(0u128, |total, calibration| match check(calibration) {
+            Ok(true) => total.checked_add(calibration.result()),
+            Ok(false) => Some(total),
+            Err(Overflow) => None,
+        })
+        .map_or("overflow".to_string(), |total| total.to_string())
+}
+
+fn part1(input: &[Calibration]) -> String {
+    total(input, Calibration::is_valid)
+}
+
+fn part2(input: &[Calibration]) -> String {
+    total(input, Calibration::is_valid_concat)
 }
 
 #[cfg(test)]
@@ -106,18 +524,185 @@ mod tests {
 21037: 9 7 18 13
 292: 11 6 16 20";
 
+    fn equations(input: &str) -> Vec<Equation<u64>> {
+        input
+            .lines()
+            .map(|line| Equation::parse(line).unwrap())
+            .collect()
+    }
+
     #[test]
     fn test_part_1() {
         let ex_answer = "3749";
-        let ex_data = parse_input(EX_INPUT);
+        let ex_data = parse_input(EX_INPUT).unwrap();
         let result = part1(&ex_data);
         assert_eq!(result, ex_answer);
     }
 
+    #[test]
+    fn test_solutions() {
+        let ex_data = equations(EX_INPUT);
+        let symbols =
+            |ops: &Vec<&Operator<u64>>| ops.iter().map(|op| op.symbol).collect::<Vec<_>>();
+
+        // 3267: 81 40 27 works as 81 + 40 * 27 and as 81 * 40 + 27
+        let mut found: Vec<_> = ex_data[1]
+            .solutions(&[Operator::ADD, Operator::MUL])
+            .unwrap()
+            .iter()
+            .map(symbols)
+            .collect();
+        found.sort();
+        assert_eq!(found, vec![vec!["*", "+"], vec!["+", "*"]]);
+
+        let operators = [Operator::ADD, Operator::MUL, Operator::CONCAT];
+        for equation in &ex_data {
+            for solution in equation.solutions(&operators).unwrap() {
+                assert_eq!(
+                    evaluate(&equation.operands, &solution),
+                    Some(equation.result)
+                );
+            }
+        }
+        assert!(ex_data[6].solutions(&operators[..2]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_explain() {
+        let ex_data = equations(EX_INPUT);
+        let operators = [Operator::ADD, Operator::MUL, Operator::CONCAT];
+
+        let witness = ex_data[3].witness(&operators).unwrap().unwrap();
+        assert_eq!(ex_data[3].expression(&witness), "156 = 15 || 6");
+        let witness = ex_data[0].witness(&operators).unwrap().unwrap();
+        assert_eq!(ex_data[0].expression(&witness), "190 = 10 * 19");
+
+        assert_eq!(ex_data[1].count_solutions(&operators), Ok(2));
+        assert_eq!(ex_data[4].count_solutions(&operators), Ok(1));
+        assert!(ex_data[2].witness(&operators).unwrap().is_none());
+        assert_eq!(
+            ex_data[2].explain(&operators).unwrap(),
+            "83: 17 5: unsatisfiable"
+        );
+    }
+
+    #[test]
+    fn test_other_operators() {
+        let equation = Equation::<u64>::parse("81: 12 3 2").unwrap();
+        let operators = [Operator::SUB, Operator::XOR, Operator::POW];
+        let found: Vec<_> = equation
+            .solutions(&operators)
+            .unwrap()
+            .into_iter()
+            .map(|ops| ops.iter().map(|op| op.symbol).collect::<Vec<_>>())
+            .collect();
+        // (12 - 3) ** 2 = 81
+        assert_eq!(found, vec![vec!["-", "**"]]);
+
+        // 0b101 written after 0b11 is 0b11101
+        let binary = Operator::<u64>::concat::<2>("||2");
+        assert_eq!((binary.apply)(0b11, 0b101), Ok(0b11101));
+        assert_eq!((binary.undo)(0b11101, 0b101), Inverse::Exactly(0b11));
+        assert_eq!((binary.undo)(0b11101, 0b111), Inverse::Never);
+    }
+
+    #[test]
+    fn test_zero_operands() {
+        let operators = [Operator::ADD, Operator::MUL, Operator::CONCAT];
+        // times zero makes whatever comes before it irrelevant, so 3 + 4, 3 * 4 and 3 || 4
+        // all lead to a solution
+        let equation = Equation::<u64>::parse("0: 3 4 0").unwrap();
+        assert_eq!(equation.count_solutions(&operators), Ok(3));
+        for solution in equation.solutions(&operators).unwrap() {
+            assert_eq!(evaluate(&equation.operands, &solution), Some(0));
+        }
+
+        // a leading zero vanishes when concatenated, and a trailing one shifts
+        let equation = Equation::<u64>::parse("5: 0 5").unwrap();
+        let witness = equation.witness(&operators[1..]).unwrap().unwrap();
+        assert_eq!(equation.expression(&witness), "5 = 0 || 5");
+        assert_eq!(
+            Equation::<u64>::parse("70: 7 0").unwrap().is_valid_concat(),
+            Ok(true)
+        );
+
+        // x ** 0 is 1 whatever x is
+        let equation = Equation::<u64>::parse("1: 2 3 0").unwrap();
+        assert_eq!(
+            equation.count_solutions(&[Operator::ADD, Operator::POW]),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        // concatenating onto 20 digits can't fit in a u64, but putting a zero in front can
+        let max = u64::MAX;
+        let equation = Equation::<u64>::parse(&format!("{max}: 0 {max}")).unwrap();
+        assert_eq!(equation.is_valid_concat(), Ok(true));
+        assert_eq!(
+            Equation::<u64>::parse(&format!("{max}: 2 {max}"))
+                .unwrap()
+                .is_valid_concat(),
+            Ok(false)
+        );
+        assert_eq!(
+            (Operator::<u64>::CONCAT.apply)(1, max),
+            Err(Failure::Overflow)
+        );
+        assert_eq!((Operator::<u64>::MUL.apply)(max, 2), Err(Failure::Overflow));
+        assert_eq!((Operator::<u64>::SUB.apply)(2, 3), Err(Failure::Undefined));
+
+        // every number fits in a u64, but both max + 2 and max * 2 overflow before the zero
+        // wipes them out, so the search has to widen to find a solution
+        let data = parse_input(&format!("0: {max} 2 0")).unwrap();
+        assert!(matches!(data[0], Calibration::Narrow(_)));
+        let Calibration::Narrow(equation) = &data[0] else {
+            unreachable!()
+        };
+        assert_eq!(equation.is_valid(), Err(Overflow));
+        assert_eq!(data[0].is_valid(), Ok(true));
+        assert_eq!(
+            data[0].explain(),
+            "0 = 18446744073709551615 + 2 * 0 (3 solutions)"
+        );
+
+        // undoing `- 1` from the max needs a 65th bit: 2^32 * 2^32 - 1
+        let equation = Equation::<u64>::parse(&format!("{max}: 4294967296 4294967296 1")).unwrap();
+        let operators = [Operator::ADD, Operator::MUL, Operator::SUB];
+        assert_eq!(equation.is_solvable(&operators), Err(Overflow));
+        assert_eq!(
+            equation
+                .widen()
+                .is_solvable(&[Operator::ADD, Operator::MUL, Operator::SUB]),
+            Ok(true)
+        );
+
+        // 2^64 = 2^32 * 2^32 needs the wide path
+        let input = "18446744073709551616: 4294967296 4294967296\n190: 10 19";
+        let data = parse_input(input).unwrap();
+        assert!(matches!(data[0], Calibration::Wide(_)));
+        assert!(matches!(data[1], Calibration::Narrow(_)));
+        assert_eq!(part1(&data), "18446744073709551806");
+
+        // and a result past u128 is rejected rather than wrapped
+        let too_big = format!("{}0: 1 2", u128::MAX);
+        assert!(parse_input(&too_big).is_err());
+        assert!(parse_input("12 3 4").is_err());
+        let max = u128::MAX;
+        let data = parse_input(&format!("{max}: {max}\n{max}: {max}")).unwrap();
+        assert_eq!(part1(&data), "overflow");
+
+        // and when even 128 bits can't decide it, that's reported rather than guessed
+        let data = parse_input(&format!("0: {max} 2 0")).unwrap();
+        assert_eq!(data[0].is_valid(), Err(Overflow));
+        assert_eq!(part1(&data), "overflow");
+    }
+
     #[test]
     fn test_part_2() {
         let ex_answer_2 = "11387";
-        let ex_data = parse_input(EX_INPUT);
+        let ex_data = parse_input(EX_INPUT).unwrap();
         let result_2 = part2(&ex_data);
         assert_eq!(result_2, ex_answer_2);
     }
@@ -1,6 +1,10 @@
 #![allow(unused_variables, dead_code, unused_imports)]
-use aoc_2024::grid::{Grid, Point};
-use std::{collections::HashSet, hash::Hash};
+use aoc_2024::grid::{Color, Grid, Overlay, Point};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    ops::RangeInclusive,
+};
 
 fn main() {
     let input = include_str!("../.inputs/input08.txt");
@@ -9,6 +13,31 @@ fn main() {
     dbg!(part1);
     let part2 = part2(&data);
     dbg!(part2);
+
+    // `--image=antinodes.ppm` draws the antennas with the antinodes of both parts
+    if let Some(path) =
+        std::env::args().find_map(|arg| arg.strip_prefix("--image=").map(String::from))
+    {
+        draw(&data, &path).unwrap();
+    }
+}
+
+/// Antinodes reaching to the edge in yellow, the part 1 pairs in red, and the antennas
+/// themselves in blue on top
+fn draw(input: &Input, path: &str) -> std::io::Result<()> {
+    let flatten = |groups: HashMap<u8, HashSet<(usize, usize)>>| {
+        groups.into_values().flatten().collect::<Vec<_>>()
+    };
+    let antennas = input.index_by_value(b".").into_values().flatten();
+    let overlays = [
+        Overlay::new(flatten(antinodes(input, &Harmonics::ToEdge)), Color::Yellow),
+        Overlay::new(
+            flatten(antinodes(input, &Harmonics::Range(1..=1))),
+            Color::Red,
+        ),
+        Overlay::new(antennas, Color::Blue),
+    ];
+    input.write_ppm(path, 4, |_| [230, 230, 230], &overlays)
 }
 
 type Input = Grid<u8>;
@@ -17,76 +46,62 @@ fn parse_input(input: &str) -> Input {
     Grid::<u8>::parse_grid_bytes(input).unwrap()
 }
 
-fn part1(input: &Input) -> String {
-    let wavelengths = input.find_unique_values(Some(vec![b'.']));
-    let mut antinode_set: HashSet<(usize, usize)> = HashSet::new();
-    wavelengths.iter().for_each(|wavelength| {
-        let locs = input.find_all(wavelength).unwrap();
-        locs.iter()
-            .enumerate()
-            .flat_map(|(i, &x)| locs[i..].iter().map(move |&y| (x, y)))
-            .for_each(|(loc_1, loc_2)| {
-                let (dx, dy) = (
-                    loc_1.0 as isize - loc_2.0 as isize,
-                    loc_1.1 as isize - loc_2.1 as isize,
-                );
-                if dx != 0 || dy != 0 {
-                    let (antinode_1, antinode_2) = (
-                        (loc_1.0 as isize + dx, loc_1.1 as isize + dy),
-                        (loc_2.0 as isize - dx, loc_2.1 as isize - dy),
-                    );
-
-                    if input.is_valid_boint(antinode_1) {
-                        let (x1, y1) = (antinode_1.0 as usize, antinode_1.1 as usize);
-                        antinode_set.insert((x1, y1));
-                    }
+/// How far out from each pair of antennas the antinodes reach
+#[derive(Clone, Debug)]
+enum Harmonics {
+    /// Antinodes `k` whole pair-offsets beyond each antenna, for every `k` in the range. `0` is
+    /// the antennas themselves and `1` is the classic pair of antinodes.
+    Range(RangeInclusive<usize>),
+    /// Every grid position exactly in line with the pair, out to both edges
+    ToEdge,
+}
 
-                    if input.is_valid_boint(antinode_2) {
-                        let (x2, y2) = (antinode_2.0 as usize, antinode_2.1 as usize);
-                        antinode_set.insert((x2, y2));
+fn antinodes(input: &Input, harmonics: &Harmonics) -> HashMap<u8, HashSet<(usize, usize)>> {
+    let mut groups = HashMap::new();
+    for (wavelength, locs) in input.index_by_value(b".") {
+        let antinode_set: &mut HashSet<(usize, usize)> = groups.entry(wavelength).or_default();
+        for (i, &loc_1) in locs.iter().enumerate() {
+            for &loc_2 in &locs[i + 1..] {
+                match harmonics {
+                    Harmonics::Range(range) => {
+                        let (dx, dy) = (
+                            loc_1.0 as isize - loc_2.0 as isize,
+                            loc_1.1 as isize - loc_2.1 as isize,
+                        );
+                        let take = if range.is_empty() {
+                            0
+                        } else {
+                            (range.end() - range.start()).saturating_add(1)
+                        };
+                        let skip = *range.start();
+                        let outward_1 = std::iter::once(loc_1).chain(input.ray(loc_1, (dx, dy)));
+                        let outward_2 = std::iter::once(loc_2).chain(input.ray(loc_2, (-dx, -dy)));
+                        antinode_set.extend(outward_1.skip(skip).take(take));
+                        antinode_set.extend(outward_2.skip(skip).take(take));
                     }
+                    Harmonics::ToEdge => antinode_set.extend(input.line(loc_1, loc_2)),
                 }
-            });
-    });
-    antinode_set.len().to_string()
+            }
+        }
+    }
+    groups
 }
 
-fn part2(input: &Input) -> String {
-    let wavelengths = input.find_unique_values(Some(vec![b'.']));
-    let mut antinode_set: HashSet<(usize, usize)> = HashSet::new();
-    wavelengths.iter().for_each(|wavelength| {
-        let locs = input.find_all(wavelength).unwrap();
-        locs.iter()
-            .enumerate()
-            .flat_map(|(i, &x)| locs[i..].iter().map(move |&y| (x, y)))
-            .for_each(|(loc_1, loc_2)| {
-                antinode_set.insert(loc_1);
-                antinode_set.insert(loc_2);
-                let (dx, dy) = (
-                    loc_1.0 as isize - loc_2.0 as isize,
-                    loc_1.1 as isize - loc_2.1 as isize,
-                );
-                if dx != 0 || dy != 0 {
-                    let (mut antinode_1, mut antinode_2) = (
-                        (loc_1.0 as isize + dx, loc_1.1 as isize + dy),
-                        (loc_2.0 as isize - dx, loc_2.1 as isize - dy),
-                    );
+/// Number of distinct positions holding an antinode of any frequency
+fn count_antinodes(input: &Input, harmonics: &Harmonics) -> usize {
+    antinodes(input, harmonics)
+        .into_values()
+        .flatten()
+        .collect::<HashSet<_>>()
+        .len()
+}
 
-                    while input.is_valid_boint(antinode_1) {
-                        let (x1, y1) = (antinode_1.0 as usize, antinode_1.1 as usize);
-                        antinode_set.insert((x1, y1));
-                        antinode_1 = (antinode_1.0 as isize + dx, antinode_1.1 as isize + dy);
-                    }
+fn part1(input: &Input) -> String {
+    count_antinodes(input, &Harmonics::Range(1..=1)).to_string()
+}
 
-                    while input.is_valid_boint(antinode_2) {
-                        let (x2, y2) = (antinode_2.0 as usize, antinode_2.1 as usize);
-                        antinode_set.insert((x2, y2));
-                        antinode_2 = (antinode_2.0 as isize - dx, antinode_2.1 as isize - dy);
-                    }
-                }
-            });
-    });
-    antinode_set.len().to_string()
+fn part2(input: &Input) -> String {
+    count_antinodes(input, &Harmonics::ToEdge).to_string()
 }
 
 #[cfg(test)]
@@ -113,6 +128,19 @@ mod tests {
         assert_eq!(result, ex_answer);
     }
 
+    #[test]
+    fn test_harmonics() {
+        let ex_data = parse_input(EX_INPUT);
+        let groups = antinodes(&ex_data, &Harmonics::Range(0..=0));
+        assert_eq!(groups[&b'0'].len(), 4);
+        assert_eq!(groups[&b'A'].len(), 3);
+        assert_eq!(count_antinodes(&ex_data, &Harmonics::Range(0..=1)), 20);
+        assert_eq!(
+            count_antinodes(&ex_data, &Harmonics::Range(0..=usize::MAX)),
+            count_antinodes(&ex_data, &Harmonics::ToEdge)
+        );
+    }
+
     #[test]
     fn test_part_2() {
         let ex_answer_2 = "34";
@@ -1,176 +1,328 @@
 #![allow(unused_variables, dead_code, unused_imports)]
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::fmt;
 
 fn main() {
     let input = include_str!("../.inputs/input09.txt");
-    let data = parse_input(input);
+    let data = parse_input(input).unwrap_or_else(|err| panic!("{err}"));
     let part1 = part1(&data);
     dbg!(part1);
     let part2 = part2(&data);
     dbg!(part2);
+
+    // `--compare` runs every compaction policy over the same disk
+    if std::env::args().any(|arg| arg == "--compare") {
+        let strategies: [&dyn CompactionStrategy; 4] =
+            [&Fragmenting, &FirstFit, &BestFit, &WorstFit];
+        for strategy in strategies {
+            let report = Disk::new(&data).compact(strategy);
+            println!("{:>12}: {:?}", strategy.name(), report);
+        }
+    }
 }
 
 type Input = Vec<u8>;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum Block {
-    Empty,
-    File(u16),
+/// A contiguous run of blocks belonging to one file
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Extent {
+    start: usize,
+    len: usize,
+    id: usize,
 }
-impl fmt::Display for Block {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Block::Empty => write!(f, "."),
-            Block::File(id) => write!(f, "{}", id),
-        }
+
+impl Extent {
+    /// Sum of position * id over every block, using the arithmetic series
+    /// start + (start + 1) + ... + (start + len - 1) = len * start + len * (len - 1) / 2
+    fn checksum(&self) -> u128 {
+        let (start, len) = (self.start as u128, self.len as u128);
+        self.id as u128 * (len * start + len * len.saturating_sub(1) / 2)
     }
 }
 
+/// A contiguous run of empty blocks
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Span {
+    start: usize,
+    len: usize,
+}
+
+/// The disk as a list of file extents and the free spans between them, rather than one entry
+/// per block. `free` is kept sorted by position.
 struct Disk {
-    data: Vec<Block>,
+    files: Vec<Extent>,
+    free: Vec<Span>,
 }
 
 impl Disk {
-    fn compress(&mut self) {
-        let (mut i, mut j) = (0usize, self.data.len() - 1);
-        while i < j {
-            // find next empty spot
-            while self.data[i] != Block::Empty {
-                i += 1;
-            }
-            // find next value that needs to move
-            while self.data[j] == Block::Empty {
-                j -= 1;
-            }
-            if i < j {
-                self.data[i] = self.data[j];
-                self.data[j] = Block::Empty;
+    fn new(input: &Input) -> Self {
+        let (mut files, mut free) = (Vec::new(), Vec::new());
+        let mut position = 0;
+        for (i, &len) in input.iter().enumerate() {
+            let len = len as usize;
+            if i % 2 == 0 {
+                files.push(Extent {
+                    start: position,
+                    len,
+                    id: i / 2,
+                });
+            } else if len > 0 {
+                free.push(Span {
+                    start: position,
+                    len,
+                });
             }
+            position += len;
         }
+        Disk { files, free }
     }
 
-    fn find_empty_spans(&self) -> Vec<(usize, usize)> {
-        let mut spans = Vec::new();
-        let mut i = 0;
+    /// Runs a compaction policy and reports what it cost and how tidy the disk ended up
+    fn compact(&mut self, strategy: &dyn CompactionStrategy) -> CompactionReport {
+        let mut report = CompactionReport::default();
+        strategy.compact(self, &mut report);
+        self.rebuild_free();
 
-        while i < self.data.len() {
-            if self.data[i] == Block::Empty {
-                let start = i;
-                while i < self.data.len() && self.data[i] == Block::Empty {
-                    i += 1;
-                }
-                spans.push((start, i));
-            } else {
-                i += 1;
-            }
+        // IDs are dense, so a vector counts extents far faster than a map on big disks
+        let ids = self
+            .files
+            .iter()
+            .map(|extent| extent.id + 1)
+            .max()
+            .unwrap_or(0);
+        let mut extents_per_file = vec![0usize; ids];
+        for extent in self.files.iter().filter(|extent| extent.len > 0) {
+            extents_per_file[extent.id] += 1;
         }
+        report.split_files = extents_per_file.iter().filter(|&&n| n > 1).count();
+        report.free_gaps = self.free.len();
+        report.checksum = self.checksum();
+        report
+    }
 
-        spans
+    /// Free spans bucketed by length, each a min-heap of start positions, so the leftmost span
+    /// of any given length is always a peek away
+    fn free_buckets(&self) -> Vec<BinaryHeap<Reverse<usize>>> {
+        let max_len = self.free.iter().map(|span| span.len).max().unwrap_or(0);
+        let mut buckets = vec![BinaryHeap::new(); max_len + 1];
+        for span in &self.free {
+            buckets[span.len].push(Reverse(span.start));
+        }
+        buckets
     }
-    fn find_file_chunks(&self) -> Vec<(usize, usize, u16)> {
-        let mut chunks = Vec::new();
-        let mut i = 0;
 
-        while i < self.data.len() {
-            if let Block::File(id) = self.data[i] {
-                let start = i;
-                while i < self.data.len() && self.data[i] == Block::File(id) {
-                    i += 1;
-                }
-                chunks.push((start, i, id));
-            } else {
-                i += 1;
+    /// Recomputes the free spans as the gaps between file extents
+    fn rebuild_free(&mut self) {
+        self.files.sort_by_key(|extent| extent.start);
+        self.free.clear();
+        let mut position = 0;
+        for extent in &self.files {
+            if extent.start > position {
+                self.free.push(Span {
+                    start: position,
+                    len: extent.start - position,
+                });
             }
+            position = position.max(extent.start + extent.len);
         }
+    }
+
+    fn checksum(&self) -> u128 {
+        self.files.iter().map(Extent::checksum).sum()
+    }
+}
 
-        chunks
+impl fmt::Display for Disk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut files = self.files.clone();
+        files.sort_by_key(|extent| extent.start);
+        let mut position = 0;
+        for extent in files {
+            for _ in position..extent.start {
+                write!(f, ".")?;
+            }
+            for _ in 0..extent.len {
+                write!(f, "{}", extent.id)?;
+            }
+            position = extent.start + extent.len;
+        }
+        Ok(())
     }
+}
 
-    /// an assuredly slow way to do this
-    fn compress_2(&mut self) {
-        let mut empty_spans = self.find_empty_spans();
-        let mut file_chunks = self.find_file_chunks();
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CompactionReport {
+    /// Number of times a run of blocks was picked up and put down somewhere else
+    moves: usize,
+    blocks_moved: usize,
+    /// Files left in more than one piece
+    split_files: usize,
+    /// Runs of free space left between files
+    free_gaps: usize,
+    checksum: u128,
+}
 
-        file_chunks.sort_by(|a, b| b.2.cmp(&a.2));
+/// A policy for squeezing files towards the start of the disk
+trait CompactionStrategy {
+    fn name(&self) -> &'static str;
+    fn compact(&self, disk: &mut Disk, report: &mut CompactionReport);
+}
 
-        for (start, end, id) in file_chunks {
-            let chunk_size = end - start;
+/// Moves blocks one at a time from the end of the disk into the leftmost free space, splitting
+/// files wherever they have to.
+struct Fragmenting;
 
-            if let Some((span_start, span_end)) =
-                empty_spans.iter().find(|&&(span_start, span_end)| {
-                    (span_end - span_start) >= chunk_size && span_start < start
-                })
-            {
-                let span_start = *span_start;
-                for k in 0..chunk_size {
-                    self.data[span_start + k] = Block::File(id);
-                }
+impl CompactionStrategy for Fragmenting {
+    fn name(&self) -> &'static str {
+        "fragmenting"
+    }
 
-                for k in start..end {
-                    self.data[k] = Block::Empty;
+    fn compact(&self, disk: &mut Disk, report: &mut CompactionReport) {
+        let mut remaining = std::mem::take(&mut disk.files);
+        remaining.sort_by_key(|extent| extent.start);
+        let mut placed = Vec::new();
+
+        for span in &disk.free {
+            let mut span = *span;
+            while span.len > 0 {
+                let Some(last) = remaining.last_mut() else {
+                    break;
+                };
+                if last.start < span.start {
+                    break;
+                }
+                let moved = span.len.min(last.len);
+                placed.push(Extent {
+                    start: span.start,
+                    len: moved,
+                    id: last.id,
+                });
+                report.moves += 1;
+                report.blocks_moved += moved;
+                span.start += moved;
+                span.len -= moved;
+                last.len -= moved;
+                if last.len == 0 {
+                    remaining.pop();
                 }
-                empty_spans = self.find_empty_spans();
             }
         }
+        placed.extend(remaining.into_iter().filter(|extent| extent.len > 0));
+        disk.files = placed;
     }
+}
 
-    fn checksum(&self) -> u64 {
-        let mut result = 0;
-        for (i, block) in self.data.iter().enumerate() {
-            if let Block::File(value) = block {
-                result += i as u64 * *value as u64;
-            }
+/// Moves whole files, highest ID first, into a free span to their left, leaving them where
+/// they are if nothing fits. `pick` chooses among the candidate spans, given as (start, len)
+/// for the leftmost span of each length that fits.
+fn move_whole_files(
+    disk: &mut Disk,
+    report: &mut CompactionReport,
+    pick: impl Fn(&mut dyn Iterator<Item = (usize, usize)>) -> Option<(usize, usize)>,
+) {
+    let mut buckets = disk.free_buckets();
+    let max_len = buckets.len() - 1;
+
+    disk.files.sort_by_key(|extent| Reverse(extent.id));
+    for file in disk.files.iter_mut().filter(|file| file.len > 0) {
+        let mut candidates = (file.len..=max_len).filter_map(|len| {
+            buckets[len]
+                .peek()
+                .map(|&Reverse(start)| (start, len))
+                .filter(|&(start, _)| start < file.start)
+        });
+        let Some((span_start, span_len)) = pick(&mut candidates) else {
+            continue;
+        };
+        buckets[span_len].pop();
+        file.start = span_start;
+        report.moves += 1;
+        report.blocks_moved += file.len;
+        // the space the file leaves behind is to the right of every file still to move, so it
+        // never needs to go back in a bucket
+        let leftover = span_len - file.len;
+        if leftover > 0 {
+            buckets[leftover].push(Reverse(span_start + file.len));
         }
-        result
     }
 }
 
-impl fmt::Display for Disk {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for block in &self.data {
-            write!(f, "{}", block)?;
-        }
-        Ok(())
+/// Whole files into the leftmost span that fits
+struct FirstFit;
+
+impl CompactionStrategy for FirstFit {
+    fn name(&self) -> &'static str {
+        "first-fit"
+    }
+
+    fn compact(&self, disk: &mut Disk, report: &mut CompactionReport) {
+        move_whole_files(disk, report, |candidates| candidates.min())
     }
 }
 
-fn parse_input(raw: &str) -> Input {
-    raw.trim()
-        .chars()
-        .map(|c| c.to_string().parse::<u8>().unwrap())
-        .collect()
+/// Whole files into the smallest span that fits, leftmost among equals
+struct BestFit;
+
+impl CompactionStrategy for BestFit {
+    fn name(&self) -> &'static str {
+        "best-fit"
+    }
+
+    fn compact(&self, disk: &mut Disk, report: &mut CompactionReport) {
+        move_whole_files(disk, report, |candidates| candidates.next())
+    }
 }
 
-fn expand_input(input: &Input) -> Disk {
-    let first = input[0];
-    let mut id = 1u16;
-    let mut expanded = Vec::<Block>::new();
-    for _ in 0..first {
-        expanded.push(Block::File(0));
+/// Whole files into the largest span to their left, leftmost among equals
+struct WorstFit;
+
+impl CompactionStrategy for WorstFit {
+    fn name(&self) -> &'static str {
+        "worst-fit"
     }
-    input[1..].chunks(2).for_each(|chunk| {
-        let (space_length, file_length) = (chunk[0], chunk[1]);
-        for _ in 0..space_length {
-            expanded.push(Block::Empty);
-        }
-        for _ in 0..file_length {
-            expanded.push(Block::File(id));
-        }
-        id += 1;
-    });
-    Disk { data: expanded }
+
+    fn compact(&self, disk: &mut Disk, report: &mut CompactionReport) {
+        move_whole_files(disk, report, |candidates| candidates.last())
+    }
+}
+
+/// A byte in the disk map that isn't a digit
+#[derive(Debug, PartialEq, Eq)]
+struct BadDigit {
+    offset: usize,
+    byte: u8,
+}
+
+impl fmt::Display for BadDigit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Expected a digit at byte {}, found {:?}",
+            self.offset, self.byte as char
+        )
+    }
+}
+
+fn parse_input(raw: &str) -> Result<Input, BadDigit> {
+    raw.trim_ascii_end()
+        .bytes()
+        .enumerate()
+        .map(|(offset, byte)| match byte {
+            b'0'..=b'9' => Ok(byte - b'0'),
+            _ => Err(BadDigit { offset, byte }),
+        })
+        .collect()
 }
 
 fn part1(input: &Input) -> String {
-    let mut disk = expand_input(input);
-    disk.compress();
-    disk.checksum().to_string()
+    let mut disk = Disk::new(input);
+    disk.compact(&Fragmenting).checksum.to_string()
 }
 
 fn part2(input: &Input) -> String {
-    let mut disk = expand_input(input);
-    disk.compress_2();
-    disk.checksum().to_string()
+    let mut disk = Disk::new(input);
+    disk.compact(&FirstFit).checksum.to_string()
 }
 
 #[cfg(test)]
@@ -181,15 +333,174 @@ mod tests {
     #[test]
     fn test_part_1() {
         let ex_answer = "1928";
-        let ex_data = parse_input(EX_INPUT);
+        let ex_data = parse_input(EX_INPUT).unwrap();
         let result = part1(&ex_data);
         assert_eq!(result, ex_answer);
     }
 
+    #[test]
+    fn test_layout() {
+        let ex_data = parse_input(EX_INPUT).unwrap();
+        let mut disk = Disk::new(&ex_data);
+        assert_eq!(
+            disk.to_string(),
+            "00...111...2...333.44.5555.6666.777.888899"
+        );
+        disk.compact(&Fragmenting);
+        assert_eq!(disk.to_string(), "0099811188827773336446555566");
+
+        let mut disk = Disk::new(&ex_data);
+        disk.compact(&FirstFit);
+        assert_eq!(disk.to_string(), "00992111777.44.333....5555.6666.....8888");
+    }
+
+    #[test]
+    fn test_strategies() {
+        let ex_data = parse_input(EX_INPUT).unwrap();
+        let report = Disk::new(&ex_data).compact(&Fragmenting);
+        assert_eq!(report.blocks_moved, 12);
+        assert_eq!(report.free_gaps, 0);
+        assert_eq!(report.split_files, 2);
+
+        let report = Disk::new(&ex_data).compact(&FirstFit);
+        assert_eq!((report.moves, report.split_files), (4, 0));
+        assert_eq!(report.free_gaps, 5);
+
+        // a lone block prefers the snug gap to the first big one, and worst-fit the opposite
+        let mut disk = Disk::new(&parse_input("13111").unwrap());
+        disk.compact(&BestFit);
+        assert_eq!(disk.to_string(), "01...2");
+        let mut disk = Disk::new(&parse_input("13111").unwrap());
+        disk.compact(&FirstFit);
+        assert_eq!(disk.to_string(), "021");
+
+        let mut disk = Disk::new(&parse_input("11131").unwrap());
+        disk.compact(&WorstFit);
+        assert_eq!(disk.to_string(), "01.2");
+    }
+
+    #[test]
+    fn test_bad_digit() {
+        assert_eq!(parse_input("12345\n").unwrap(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            parse_input("12a4"),
+            Err(BadDigit {
+                offset: 2,
+                byte: b'a'
+            })
+        );
+    }
+
+    /// A pseudo-random disk map of `len` digits
+    fn generate_map(len: usize, mut seed: u64) -> String {
+        (0..len)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (b'0' + (seed >> 33) as u8 % 10) as char
+            })
+            .collect()
+    }
+
+    /// Compacts `input` both ways and checks what must hold whatever the map: fragmenting
+    /// packs every file block into the front of the disk with each file keeping its size, and
+    /// whole-file moves keep every file intact, only ever move left, and never overlap.
+    /// Returns the fragmenting and first-fit checksums.
+    fn check_invariants(input: &Input) -> (u128, u128) {
+        let before = Disk::new(input).files;
+        let file_blocks: usize = before.iter().map(|extent| extent.len).sum();
+
+        // compacting leaves the extents sorted by start
+        let mut disk = Disk::new(input);
+        let report = disk.compact(&Fragmenting);
+        assert_eq!(report.free_gaps, 0);
+        let mut end = 0;
+        let mut placed = vec![0; before.len()];
+        for extent in &disk.files {
+            assert_eq!(extent.start, end);
+            end += extent.len;
+            placed[extent.id] += extent.len;
+        }
+        assert_eq!(end, file_blocks);
+        assert!(placed
+            .iter()
+            .zip(&before)
+            .all(|(&len, file)| len == file.len));
+
+        let mut disk = Disk::new(input);
+        let first_fit = disk.compact(&FirstFit);
+        assert_eq!(disk.files.len(), before.len());
+        let mut moved = vec![None; before.len()];
+        for extent in &disk.files {
+            moved[extent.id] = Some(*extent);
+        }
+        for (extent, file) in moved.iter().zip(&before) {
+            let extent = extent.unwrap();
+            assert_eq!(extent.len, file.len);
+            assert!(extent.start <= file.start);
+        }
+        // empty files take up no room, so they may share a start with anything
+        let extents: Vec<_> = disk.files.iter().filter(|extent| extent.len > 0).collect();
+        assert!(extents
+            .windows(2)
+            .all(|pair| pair[0].start + pair[0].len <= pair[1].start));
+        (report.checksum, first_fit.checksum)
+    }
+
+    /// The fragmenting checksum worked out one block at a time
+    fn fragmenting_reference(input: &Input) -> u128 {
+        let blocks: usize = input.iter().map(|&len| len as usize).sum();
+        let mut layout: Vec<Option<u32>> = Vec::with_capacity(blocks);
+        for (i, &len) in input.iter().enumerate() {
+            let block = (i % 2 == 0).then_some((i / 2) as u32);
+            layout.extend(std::iter::repeat_n(block, len as usize));
+        }
+        let (mut left, mut right) = (0, layout.len() - 1);
+        while left < right {
+            match (layout[left], layout[right]) {
+                (Some(_), _) => left += 1,
+                (_, None) => right -= 1,
+                (None, Some(_)) => layout.swap(left, right),
+            }
+        }
+        layout
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, block)| block.map(|id| pos as u128 * id as u128))
+            .sum()
+    }
+
+    #[test]
+    fn test_large_maps() {
+        // 2 MB of digits is a million files, well past what a u16 ID could name
+        let input = parse_input(&generate_map(2_000_000, 9)).unwrap();
+        check_invariants(&input);
+
+        // 2m nine-block files with nine-block gaps between them: both strategies fill gap k
+        // with file 2m - 1 - k, so slot 2k holds file k and slot 2k + 1 holds file 2m - 1 - k.
+        // Slot s covers blocks 9s to 9s + 8, so the checksum is 81 * sum(s * id) + 36 * sum(id),
+        // with sum(s * id) = (2m - 1) * m^2 - m * (m - 1) / 2 and sum(id) = m * (2m - 1).
+        // 2.4 MB of digits is enough for that to overflow a u64.
+        let m: u128 = 600_000;
+        let input = parse_input(&"9".repeat(4 * m as usize - 1)).unwrap();
+        let by_slot = (2 * m - 1) * m * m - m * (m - 1) / 2;
+        let expected = 81 * by_slot + 36 * m * (2 * m - 1);
+        assert!(expected > u64::MAX as u128);
+        assert_eq!(check_invariants(&input), (expected, expected));
+    }
+
+    #[test]
+    #[ignore = "block-by-block reference is slow in debug builds, run with --ignored"]
+    fn test_large_map_reference() {
+        let input = parse_input(&generate_map(4_000_000, 9)).unwrap();
+        assert_eq!(check_invariants(&input).0, fragmenting_reference(&input));
+    }
+
     #[test]
     fn test_part_2() {
         let ex_answer_2 = "2858";
-        let ex_data = parse_input(EX_INPUT);
+        let ex_data = parse_input(EX_INPUT).unwrap();
         let result2 = part2(&ex_data);
         assert_eq!(result2, ex_answer_2);
     }
@@ -27,11 +27,12 @@ fn full_trails(grid: &Grid<u8>, start_pos: Point) -> Vec<Point> {
             point.move_by(-1, 0),
             point.move_by(0, 1),
             point.move_by(0, -1),
-        ] {
-            if let Some(new_point) = new_point {
-                if grid.is_valid_point(new_point) && grid[new_point] == grid[point] + 1 {
-                    points.push_back(new_point);
-                }
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if grid.is_valid_point(new_point) && grid[new_point] == grid[point] + 1 {
+                points.push_back(new_point);
             }
         }
     }
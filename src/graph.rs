@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+
+/// A directed graph over hashable nodes. Nodes keep the order they were first added in, which
+/// is also how ties are broken when sorting, so results never depend on hash order.
+#[derive(Debug, Clone)]
+pub struct DiGraph<N> {
+    nodes: Vec<N>,
+    index: HashMap<N, usize>,
+    edges: HashSet<(usize, usize)>,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl<N> Default for DiGraph<N> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            edges: HashSet::new(),
+            successors: Vec::new(),
+            predecessors: Vec::new(),
+        }
+    }
+}
+
+/// A cycle found while sorting, listed in edge order: each node has an edge to the next, and
+/// the last has an edge back to the first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<N>(pub Vec<N>);
+
+impl<N: fmt::Display> fmt::Display for Cycle<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.0 {
+            write!(f, "{node} -> ")?;
+        }
+        match self.0.first() {
+            Some(first) => write!(f, "{first}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<N: Clone + Eq + Hash> DiGraph<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `node` if it isn't already in the graph, returning its index either way
+    fn insert(&mut self, node: N) -> usize {
+        if let Some(&i) = self.index.get(&node) {
+            return i;
+        }
+        let i = self.nodes.len();
+        self.index.insert(node.clone(), i);
+        self.nodes.push(node);
+        self.successors.push(Vec::new());
+        self.predecessors.push(Vec::new());
+        i
+    }
+
+    pub fn add_node(&mut self, node: N) {
+        self.insert(node);
+    }
+
+    /// Adds an edge from `from` to `to`, adding either node if it's new. Repeated edges are
+    /// only stored once.
+    pub fn add_edge(&mut self, from: N, to: N) {
+        let (a, b) = (self.insert(from), self.insert(to));
+        if self.edges.insert((a, b)) {
+            self.successors[a].push(b);
+            self.predecessors[b].push(a);
+        }
+    }
+
+    pub fn contains_node(&self, node: &N) -> bool {
+        self.index.contains_key(node)
+    }
+
+    pub fn contains_edge(&self, from: &N, to: &N) -> bool {
+        match (self.index.get(from), self.index.get(to)) {
+            (Some(&a), Some(&b)) => self.edges.contains(&(a, b)),
+            _ => false,
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Nodes in the order they were added
+    pub fn nodes(&self) -> impl Iterator<Item = &N> {
+        self.nodes.iter()
+    }
+
+    /// Nodes with an edge from `node`
+    pub fn successors(&self, node: &N) -> impl Iterator<Item = &N> {
+        self.neighbors(node, &self.successors)
+    }
+
+    /// Nodes with an edge to `node`
+    pub fn predecessors(&self, node: &N) -> impl Iterator<Item = &N> {
+        self.neighbors(node, &self.predecessors)
+    }
+
+    fn neighbors<'a>(
+        &'a self,
+        node: &N,
+        adjacency: &'a [Vec<usize>],
+    ) -> impl Iterator<Item = &'a N> {
+        self.index
+            .get(node)
+            .into_iter()
+            .flat_map(move |&i| adjacency[i].iter().map(|&j| &self.nodes[j]))
+    }
+
+    /// The graph restricted to `nodes` and the edges between them. Nodes that aren't in this
+    /// graph are added with no edges.
+    pub fn subgraph<'a>(&self, nodes: impl IntoIterator<Item = &'a N>) -> Self
+    where
+        N: 'a,
+    {
+        let mut sub = Self::new();
+        for node in nodes {
+            sub.add_node(node.clone());
+        }
+        for (a, node) in sub.nodes.clone().iter().enumerate() {
+            for next in self.successors(node) {
+                if let Some(&b) = sub.index.get(next) {
+                    sub.edges.insert((a, b));
+                    sub.successors[a].push(b);
+                    sub.predecessors[b].push(a);
+                }
+            }
+        }
+        sub
+    }
+
+    /// Orders every node so all edges point forwards, using Kahn's algorithm. Nodes with no
+    /// ordering between them keep the order they were added in. If the edges loop back on
+    /// themselves there is no such order, and one of the cycles is returned instead.
+    pub fn topological_sort(&self) -> Result<Vec<N>, Cycle<N>> {
+        let mut in_degree: Vec<usize> = self.predecessors.iter().map(Vec::len).collect();
+        let mut ready: VecDeque<usize> = (0..self.nodes.len())
+            .filter(|&i| in_degree[i] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(i) = ready.pop_front() {
+            order.push(self.nodes[i].clone());
+            for &j in &self.successors[i] {
+                in_degree[j] -= 1;
+                if in_degree[j] == 0 {
+                    ready.push_back(j);
+                }
+            }
+        }
+        if order.len() == self.nodes.len() {
+            return Ok(order);
+        }
+        Err(self.find_cycle(&in_degree))
+    }
+
+    /// Every node Kahn's algorithm couldn't place still has an unplaced predecessor, so
+    /// walking backwards from one of them has to come round to a node already visited
+    fn find_cycle(&self, in_degree: &[usize]) -> Cycle<N> {
+        let start = in_degree.iter().position(|&d| d > 0).unwrap();
+        let mut seen = vec![None; self.nodes.len()];
+        let mut path = Vec::new();
+        let mut i = start;
+        while seen[i].is_none() {
+            seen[i] = Some(path.len());
+            path.push(i);
+            i = *self.predecessors[i]
+                .iter()
+                .find(|&&p| in_degree[p] > 0)
+                .unwrap();
+        }
+        // the walk went against the edges, so the loop reads backwards
+        let mut cycle: Vec<N> = path[seen[i].unwrap()..]
+            .iter()
+            .map(|&j| self.nodes[j].clone())
+            .collect();
+        cycle.reverse();
+        Cycle(cycle)
+    }
+
+    /// Whether `sequence` respects every edge between the nodes in it, i.e. no node comes
+    /// after one of its successors. Nodes the graph doesn't know are unconstrained.
+    pub fn is_consistent(&self, sequence: &[N]) -> bool {
+        let mut placed = HashSet::with_capacity(sequence.len());
+        for node in sequence {
+            if self.successors(node).any(|next| placed.contains(next)) {
+                return false;
+            }
+            placed.insert(node);
+        }
+        true
+    }
+
+    /// Every edge `sequence` goes against, as `(i, j)` position pairs with `i < j` where
+    /// `sequence[j]` has an edge to `sequence[i]`, sorted by position
+    pub fn violations(&self, sequence: &[N]) -> Vec<(usize, usize)> {
+        let position: HashMap<&N, usize> = sequence
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node, i))
+            .collect();
+        let mut violations: Vec<_> = sequence
+            .iter()
+            .enumerate()
+            .flat_map(|(j, node)| {
+                self.successors(node)
+                    .filter_map(|next| position.get(next))
+                    .filter(move |&&i| i < j)
+                    .map(move |&i| (i, j))
+            })
+            .collect();
+        violations.sort();
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(u8, u8)]) -> DiGraph<u8> {
+        let mut graph = DiGraph::new();
+        for &(from, to) in edges {
+            graph.add_edge(from, to);
+        }
+        graph
+    }
+
+    #[test]
+    fn sort_and_check() {
+        let rules = graph(&[(3, 1), (1, 2), (3, 2), (4, 2), (1, 2)]);
+        assert_eq!((rules.node_count(), rules.edge_count()), (4, 4));
+        assert_eq!(rules.topological_sort(), Ok(vec![3, 4, 1, 2]));
+        assert!(rules.contains_edge(&3, &1) && !rules.contains_edge(&1, &3));
+        assert_eq!(rules.predecessors(&2).collect::<Vec<_>>(), vec![&1, &3, &4]);
+
+        assert!(rules.is_consistent(&[3, 1, 2]));
+        assert!(rules.is_consistent(&[4, 9, 3]));
+        assert!(!rules.is_consistent(&[1, 3]));
+
+        assert_eq!(rules.violations(&[2, 1, 3]), vec![(0, 1), (0, 2), (1, 2)]);
+        assert!(rules.violations(&[3, 1, 2]).is_empty());
+
+        let sub = rules.subgraph(&[2, 1]);
+        assert_eq!(sub.edge_count(), 1);
+        assert_eq!(sub.topological_sort(), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn report_cycle() {
+        let rules = graph(&[(1, 2), (2, 3), (3, 4), (4, 2), (0, 1)]);
+        let cycle = rules.topological_sort().unwrap_err();
+        assert_eq!(cycle.0.len(), 3);
+        for (i, &node) in cycle.0.iter().enumerate() {
+            assert!(rules.contains_edge(&node, &cycle.0[(i + 1) % 3]));
+        }
+        assert_eq!(Cycle(vec![2, 3, 4]).to_string(), "2 -> 3 -> 4 -> 2");
+
+        // the cycle only exists among pages that aren't in the subgraph
+        assert!(rules.subgraph(&[1, 2, 3]).topological_sort().is_ok());
+    }
+}
@@ -1,8 +1,52 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
 use std::ops::{Index, IndexMut};
-use std::{fmt, usize};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+mod automaton;
+mod bits;
+mod grid3;
+mod hex;
+mod image;
+mod ray;
+mod render;
+pub mod search;
+mod sparse;
+mod torus;
+mod visit;
+pub use automaton::{Automaton, Cycle};
+pub use bits::BitGrid;
+pub use grid3::{Axis, Coord3, Grid3, NEIGHBORS_6};
+pub use hex::{Hex, HexGrid, HexLayout, HexOffset};
+pub use render::{Animation, Color, Overlay, Renderer};
+pub use search::Board;
+pub use sparse::{ICoord, SparseGrid};
+pub use torus::{Torus, TorusRay};
+pub use visit::VisitSet;
+
+/// (dx, dy) offsets of the orthogonal neighbors: up, down, left, right
+pub const NEIGHBORS_4: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+/// (dx, dy) offsets of the orthogonal neighbors followed by the diagonal ones
+pub const NEIGHBORS_8: [(isize, isize); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+pub(crate) fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Grid<T> {
     data: Vec<Vec<T>>,
     pub width: usize,
@@ -75,18 +119,7 @@ impl<T: Clone + PartialEq> Grid<T> {
     pub fn check_directions(&self, x: usize, y: usize, n: usize) -> Vec<Vec<T>> {
         let mut directions = Vec::new();
 
-        let deltas = [
-            (0, -1),  // Up
-            (0, 1),   // Down
-            (-1, 0),  // Left
-            (1, 0),   // Right
-            (-1, -1), // Up-left
-            (1, -1),  // Up-right
-            (-1, 1),  // Down-left
-            (1, 1),   // Down-right
-        ];
-
-        for &(dx, dy) in &deltas {
+        for &(dx, dy) in &NEIGHBORS_8 {
             let mut path = Vec::new();
             for i in 0..n {
                 let nx = x as isize + i as isize * dx;
@@ -103,7 +136,25 @@ impl<T: Clone + PartialEq> Grid<T> {
 
         directions
     }
+}
 
+impl<T: Clone + Hash + Eq> Grid<T> {
+    /// Groups the positions of every value in the grid, in a single scan. Values in `exclusions`
+    /// (like the `.` background) are skipped.
+    pub fn index_by_value(&self, exclusions: &[T]) -> HashMap<T, Vec<(usize, usize)>> {
+        let mut index: HashMap<T, Vec<(usize, usize)>> = HashMap::new();
+        for (i, row) in self.data.iter().enumerate() {
+            for (j, point) in row.iter().enumerate() {
+                if !exclusions.contains(point) {
+                    index.entry(point.clone()).or_default().push((j, i));
+                }
+            }
+        }
+        index
+    }
+}
+
+impl<T> Grid<T> {
     //forgive me father for the sins of my laziness in refactoring
     //i'll get to it
     pub fn is_valid_boint(&self, point: (isize, isize)) -> bool {
@@ -116,6 +167,28 @@ impl<T: Clone + PartialEq> Grid<T> {
     pub fn is_valid_point(&self, point: Point) -> bool {
         point.x < self.width && point.y < self.height
     }
+
+    /// In-bounds positions next to `(x, y)` in the four orthogonal directions
+    pub fn neighbors4(&self, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(x, y, &NEIGHBORS_4)
+    }
+
+    /// In-bounds positions next to `(x, y)`, including diagonals
+    pub fn neighbors8(&self, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offsets(x, y, &NEIGHBORS_8)
+    }
+
+    fn offsets<'a>(
+        &'a self,
+        x: usize,
+        y: usize,
+        deltas: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        deltas.iter().filter_map(move |&(dx, dy)| {
+            let (nx, ny) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+            (nx < self.width && ny < self.height).then_some((nx, ny))
+        })
+    }
 }
 
 impl Grid<u8> {
@@ -123,7 +196,7 @@ impl Grid<u8> {
     pub fn parse_grid_bytes(input: &str) -> Result<Self, &'static str> {
         let data: Vec<Vec<u8>> = input.lines().map(|line| line.as_bytes().to_vec()).collect();
 
-        let width = data.get(0).map_or(0, |row| row.len());
+        let width = data.first().map_or(0, |row| row.len());
         if data.iter().any(|row| row.len() != width) {
             return Err("All lines must have the same length");
         }
@@ -149,7 +222,7 @@ impl Grid<u8> {
             data.push(row);
         }
 
-        let width = data.get(0).map_or(0, |row| row.len());
+        let width = data.first().map_or(0, |row| row.len());
         if data.iter().any(|row| row.len() != width) {
             return Err("All lines must have the same length");
         }
@@ -200,7 +273,7 @@ impl Grid<GridObject> {
             for &datum in row.iter() {
                 let point = match datum {
                     b'.' => GridObject::Empty,
-                    b'#' => GridObject::Obstacle(Hit::new()),
+                    b'#' => GridObject::Obstacle,
                     b'^' => GridObject::Guy(Direction::Up),
                     _ => GridObject::Empty,
                 };
@@ -210,7 +283,7 @@ impl Grid<GridObject> {
         }
 
         let height = data.len();
-        let width = data.get(0).map_or(0, |row| row.len());
+        let width = data.first().map_or(0, |row| row.len());
         if data.iter().any(|row| row.len() != width) {
             return Err("All lines must have the same length");
         }
@@ -222,140 +295,136 @@ impl Grid<GridObject> {
         })
     }
 
-    pub fn run_grid(&self, coords: (usize, usize)) -> Option<u32> {
-        let mut grid: Grid<GridObject> = self.clone();
-        let (mut guy_x, mut guy_y) = coords.clone();
-        let mut spaces = 0;
-        loop {
-            let current_point = grid[(guy_x, guy_y)];
-            let current_direction: Direction = if let GridObject::Guy(direction) = current_point {
-                direction
-            } else {
-                Direction::Up
-            };
-
-            let delta: (isize, isize) = match current_direction {
-                Direction::Up => (0, -1),
-                Direction::Down => (0, 1),
-                Direction::Left => (-1, 0),
-                Direction::Right => (1, 0),
-            };
-
-            let (new_x, new_y) = (guy_x as isize + delta.0, guy_y as isize + delta.1);
+    /// Steps the guard from `coords` until they leave the grid, yielding each (position, direction)
+    /// state along the way. `extra_obstacle` is treated as an obstacle without touching the grid.
+    /// A guard stuck in a loop walks forever, so pair this with a `VisitSet` or `take`.
+    pub fn walk(
+        &self,
+        coords: (usize, usize),
+        extra_obstacle: Option<(usize, usize)>,
+    ) -> GuardWalk<'_> {
+        let direction = match self[coords] {
+            GridObject::Guy(direction) => direction,
+            _ => Direction::Up,
+        };
+        GuardWalk {
+            grid: self,
+            state: Some((coords, direction)),
+            extra_obstacle,
+        }
+    }
 
-            if new_x < 0
-                || new_y < 0
-                || new_x as usize >= grid.width
-                || new_y as usize >= grid.height
-            {
-                break; // Elvis has left the building
+    /// Walks the guard, recording every state in `visited`. Returns `false` if the guard ends up
+    /// in a loop.
+    pub fn run_guard(
+        &self,
+        coords: (usize, usize),
+        extra_obstacle: Option<(usize, usize)>,
+        visited: &mut VisitSet,
+    ) -> bool {
+        for (position, direction) in self.walk(coords, extra_obstacle) {
+            if !visited.insert(position, direction) {
+                return false; // we are in a loop!
             }
+        }
+        true
+    }
 
-            let (new_x, new_y) = (new_x as usize, new_y as usize);
-
-            match &mut grid[(new_x, new_y)] {
-                GridObject::Visited => {
-                    grid[(new_x, new_y)] = GridObject::Guy(current_direction.clone());
-                    grid[(guy_x, guy_y)] = GridObject::Visited;
-                    (guy_x, guy_y) = (new_x, new_y);
-                }
-                GridObject::Empty => {
-                    grid[(new_x, new_y)] = GridObject::Guy(current_direction.clone());
-                    grid[(guy_x, guy_y)] = GridObject::Visited;
-                    (guy_x, guy_y) = (new_x, new_y);
-                    spaces += 1;
-                }
-                GridObject::Obstacle(hit_directions) => {
-                    if hit_directions.hit(current_direction) {
-                        return None; // we are in a loop!
-                    } else {
-                        let new_direction = match current_direction {
-                            Direction::Up => Direction::Right,   // turn right
-                            Direction::Right => Direction::Down, // turn down
-                            Direction::Down => Direction::Left,  // turn left
-                            Direction::Left => Direction::Up,    // turn up
-                        };
-                        grid[(guy_x, guy_y)] = GridObject::Guy(new_direction);
-                    }
-                }
-                GridObject::Guy(_) => continue,
-            }
+    /// Returns the number of distinct cells the guard visits, or `None` if they never leave.
+    pub fn run_grid(&self, coords: (usize, usize)) -> Option<u32> {
+        let mut visited = VisitSet::new(self.width, self.height);
+        if self.run_guard(coords, None, &mut visited) {
+            Some(visited.count() as u32)
+        } else {
+            None
         }
-        Some(spaces)
     }
-    pub fn part_2(&self, coords: (usize, usize)) -> u32 {
-        let mut grid: Grid<GridObject> = self.clone();
-        let (mut guy_x, mut guy_y) = coords.clone();
-        let mut obstacles = 0;
+
+    /// Finds every position where a single new obstacle would trap the guard in a loop. Only
+    /// cells on the guard's original path can change their route, so those are the only ones tried.
+    pub fn loop_obstacles(&self, coords: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut path = VisitSet::new(self.width, self.height);
+        self.run_guard(coords, None, &mut path);
+
+        let mut turns = VisitSet::new(self.width, self.height);
+        path.iter()
+            .filter(|&candidate| candidate != coords)
+            .filter(|&candidate| {
+                turns.clear();
+                self.guard_loops(coords, Some(candidate), &mut turns)
+            })
+            .collect()
+    }
+
+    /// Whether the guard gets stuck in a loop. Rather than walking every cell, this jumps straight
+    /// to the next obstacle along each ray, so `turns` only records the states before each turn.
+    pub fn guard_loops(
+        &self,
+        coords: (usize, usize),
+        extra_obstacle: Option<(usize, usize)>,
+        turns: &mut VisitSet,
+    ) -> bool {
+        let mut position = coords;
+        let mut direction = match self[coords] {
+            GridObject::Guy(direction) => direction,
+            _ => Direction::Up,
+        };
         loop {
-            let current_point = grid[(guy_x, guy_y)];
-            let current_direction: Direction = if let GridObject::Guy(direction) = current_point {
-                direction
-            } else {
-                Direction::Up
+            let (dx, dy) = direction.delta();
+            let Some(obstacle) = self.find_along(position, (dx, dy), |pos, cell| {
+                *cell == GridObject::Obstacle || extra_obstacle == Some(pos)
+            }) else {
+                return false; // Elvis has left the building
             };
+            position = (
+                (obstacle.0 as isize - dx) as usize,
+                (obstacle.1 as isize - dy) as usize,
+            );
+            if !turns.insert(position, direction) {
+                return true; // we are in a loop!
+            }
+            direction = direction.turn_right();
+        }
+    }
 
-            let delta: (isize, isize) = match current_direction {
-                Direction::Up => (0, -1),
-                Direction::Down => (0, 1),
-                Direction::Left => (-1, 0),
-                Direction::Right => (1, 0),
-            };
+    pub fn part_2(&self, coords: (usize, usize)) -> u32 {
+        self.loop_obstacles(coords).len() as u32
+    }
+}
 
-            let (new_x, new_y) = (guy_x as isize + delta.0, guy_y as isize + delta.1);
+pub struct GuardWalk<'a> {
+    grid: &'a Grid<GridObject>,
+    state: Option<((usize, usize), Direction)>,
+    extra_obstacle: Option<(usize, usize)>,
+}
 
-            if new_x < 0
-                || new_y < 0
-                || new_x as usize >= grid.width
-                || new_y as usize >= grid.height
-            {
-                break; // Elvis has left the building
-            }
+impl Iterator for GuardWalk<'_> {
+    type Item = ((usize, usize), Direction);
 
-            let (new_x, new_y) = (new_x as usize, new_y as usize);
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.state?;
+        let ((guy_x, guy_y), direction) = current;
+        let (dx, dy) = direction.delta();
+        let (new_x, new_y) = (guy_x as isize + dx, guy_y as isize + dy);
 
-            match &mut grid[(new_x, new_y)] {
-                GridObject::Visited => {
-                    grid[(new_x, new_y)] = GridObject::Guy(current_direction.clone());
-                    grid[(guy_x, guy_y)] = GridObject::Visited;
-                    (guy_x, guy_y) = (new_x, new_y);
-                }
-                GridObject::Empty => {
-                    let mut new_grid = self.clone();
-                    new_grid[(new_x, new_y)] = GridObject::Obstacle(Hit::new());
-                    if let Some(_) = new_grid.run_grid(coords) {
-                        ()
-                    } else {
-                        obstacles += 1;
-                    }
-
-                    grid[(new_x, new_y)] = GridObject::Guy(current_direction.clone());
-                    grid[(guy_x, guy_y)] = GridObject::Visited;
-                    (guy_x, guy_y) = (new_x, new_y);
-                }
-                GridObject::Obstacle(_) => {
-                    let new_direction = match current_direction {
-                        Direction::Up => Direction::Right,   // turn right
-                        Direction::Right => Direction::Down, // turn down
-                        Direction::Down => Direction::Left,  // turn left
-                        Direction::Left => Direction::Up,    // turn up
-                    };
-                    grid[(guy_x, guy_y)] = GridObject::Guy(new_direction);
-                }
-                GridObject::Guy(_) => continue,
+        self.state = if !self.grid.is_valid_boint((new_x, new_y)) {
+            None // Elvis has left the building
+        } else {
+            let next = (new_x as usize, new_y as usize);
+            if self.grid[next] == GridObject::Obstacle || self.extra_obstacle == Some(next) {
+                Some(((guy_x, guy_y), direction.turn_right()))
+            } else {
+                Some((next, direction))
             }
-        }
-        obstacles
+        };
+        Some(current)
     }
 }
 
 impl<T: fmt::Display> fmt::Display for Grid<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for row in &self.data {
-            for (i, item) in row.iter().enumerate() {
-                if i > 0 {
-                    write!(f, " ")?;
-                }
+            for item in row {
                 write!(f, "{}", item)?;
             }
             writeln!(f)?; // Move to the next line after each row
@@ -396,28 +465,26 @@ impl<T> IndexMut<Point> for Grid<T> {
 #[derive(Clone, Debug, Eq, PartialEq, Copy)]
 pub enum GridObject {
     Guy(Direction),
-    Obstacle(Hit),
+    Obstacle,
     Empty,
-    Visited,
 }
+impl GridObject {
+    pub fn symbol(&self) -> char {
+        match self {
+            GridObject::Guy(direction) => direction.symbol(),
+            GridObject::Obstacle => '#',
+            GridObject::Empty => '.',
+        }
+    }
+}
+
 impl fmt::Display for GridObject {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let representation = match self {
-            GridObject::Guy(direction) => match direction {
-                Direction::Up => "^",
-                Direction::Down => "v",
-                Direction::Left => "<",
-                Direction::Right => ">",
-            },
-            GridObject::Obstacle(_) => "#",
-            GridObject::Empty => ".",
-            GridObject::Visited => "*",
-        };
-        write!(f, "{}", representation)
+        write!(f, "{}", self.symbol())
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Copy)]
+#[derive(Clone, Debug, Eq, PartialEq, Copy, Hash)]
 pub enum Direction {
     Up,
     Right,
@@ -425,53 +492,40 @@ pub enum Direction {
     Left,
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Eq)]
-pub struct Hit {
-    up: bool,
-    left: bool,
-    right: bool,
-    down: bool,
-}
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /// (dx, dy) of a single step in this direction, with y growing downwards
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
 
-impl Hit {
-    pub fn new() -> Self {
-        Self {
-            up: false,
-            left: false,
-            right: false,
-            down: false,
+    pub fn symbol(&self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
         }
     }
-    pub fn hit(&mut self, from: Direction) -> bool {
-        //dbg!(&self);
-        //dbg!(from);
-        match from {
-            Direction::Up => {
-                if self.up {
-                    return true;
-                }
-                self.up = true;
-            }
-            Direction::Left => {
-                if self.left {
-                    return true;
-                }
-                self.left = true;
-            }
-            Direction::Right => {
-                if self.right {
-                    return true;
-                }
-                self.right = true;
-            }
-            Direction::Down => {
-                if self.down {
-                    return true;
-                }
-                self.down = true;
-            }
+
+    pub fn turn_right(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
         }
-        false
     }
 }
 
@@ -483,7 +537,7 @@ pub struct Point {
 
 impl Point {
     pub fn new(x: usize, y: usize) -> Self {
-        Point { x: x, y: y }
+        Point { x, y }
     }
     pub fn move_by(&self, dx: isize, dy: isize) -> Option<Self> {
         let new_x = self.x as isize + dx;
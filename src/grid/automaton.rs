@@ -0,0 +1,221 @@
+use super::Grid;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+impl<T: Clone> Grid<T> {
+    /// Computes the next generation of a cellular automaton. For every cell, `rule` gets the cell
+    /// and its neighbors at the given `(dx, dy)` offsets, e.g. `NEIGHBORS_4` or `NEIGHBORS_8`,
+    /// and returns the cell's next state. Neighbors line up with `neighborhood`, with `None`
+    /// for offsets that fall outside the grid.
+    pub fn step(
+        &self,
+        neighborhood: &[(isize, isize)],
+        rule: impl Fn(&T, &[Option<&T>]) -> T,
+    ) -> Self {
+        let mut next = self.clone();
+        self.step_into(&mut next, neighborhood, &rule);
+        next
+    }
+
+    /// Like `step`, but writes into an existing grid of the same size instead of allocating
+    pub fn step_into(
+        &self,
+        next: &mut Grid<T>,
+        neighborhood: &[(isize, isize)],
+        rule: impl Fn(&T, &[Option<&T>]) -> T,
+    ) {
+        assert_eq!(
+            (self.width, self.height),
+            (next.width, next.height),
+            "Buffers must be the same size"
+        );
+        let mut neighbors = Vec::with_capacity(neighborhood.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                neighbors.clear();
+                neighbors.extend(neighborhood.iter().map(|&(dx, dy)| {
+                    let (nx, ny) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+                    self.data.get(ny)?.get(nx)
+                }));
+                next.data[y][x] = rule(&self.data[y][x], &neighbors);
+            }
+        }
+    }
+}
+
+/// Where an automaton starts repeating itself: generation `start + period` equals `start`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub period: usize,
+}
+
+/// A grid evolving under a fixed local rule, double buffered so stepping never allocates
+pub struct Automaton<'a, T, F> {
+    current: Grid<T>,
+    buffer: Grid<T>,
+    neighborhood: &'a [(isize, isize)],
+    rule: F,
+    pub generation: usize,
+}
+
+impl<'a, T, F> Automaton<'a, T, F>
+where
+    T: Clone + PartialEq + Hash,
+    F: Fn(&T, &[Option<&T>]) -> T,
+{
+    pub fn new(grid: Grid<T>, neighborhood: &'a [(isize, isize)], rule: F) -> Self {
+        Self {
+            buffer: grid.clone(),
+            current: grid,
+            neighborhood,
+            rule,
+            generation: 0,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid<T> {
+        &self.current
+    }
+
+    pub fn into_grid(self) -> Grid<T> {
+        self.current
+    }
+
+    /// Advances one generation, returning whether anything changed
+    pub fn step(&mut self) -> bool {
+        self.current
+            .step_into(&mut self.buffer, self.neighborhood, &self.rule);
+        std::mem::swap(&mut self.current, &mut self.buffer);
+        self.generation += 1;
+        self.current != self.buffer
+    }
+
+    pub fn run(&mut self, generations: usize) -> &Grid<T> {
+        for _ in 0..generations {
+            self.step();
+        }
+        &self.current
+    }
+
+    /// Steps until a generation is identical to the one before it, returning that generation
+    pub fn run_until_stable(&mut self) -> usize {
+        while self.step() {}
+        self.generation
+    }
+
+    /// Steps until some generation repeats. The automaton is left on the first repeated
+    /// generation, and the generations seen along the way are kept for `state_at`.
+    pub fn find_cycle(&mut self) -> (Cycle, Vec<Grid<T>>) {
+        let offset = self.generation;
+        let mut history: Vec<Grid<T>> = Vec::new();
+        let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+        loop {
+            let hash = hash_grid(&self.current);
+            let candidates = seen.entry(hash).or_default();
+            // hashes only narrow it down, the grids themselves decide
+            if let Some(&start) = candidates.iter().find(|&&i| history[i] == self.current) {
+                let cycle = Cycle {
+                    start: start + offset,
+                    period: history.len() - start,
+                };
+                return (cycle, history);
+            }
+            candidates.push(history.len());
+            history.push(self.current.clone());
+            self.step();
+        }
+    }
+
+    /// The grid after `generation` total steps, extrapolating through the cycle instead of
+    /// simulating, so a billion generations cost no more than finding the cycle. The automaton
+    /// is put back where it was afterwards, so this can be asked about any generation from the
+    /// current one on, any number of times.
+    pub fn state_at(&mut self, generation: u64) -> Grid<T> {
+        let offset = self.generation;
+        assert!(generation as usize >= offset, "Can't step backwards");
+        let start = self.current.clone();
+        let (cycle, history) = self.find_cycle();
+        self.current = start;
+        self.generation = offset;
+        let generation = generation as usize;
+        let index = if generation < cycle.start {
+            generation
+        } else {
+            cycle.start + (generation - cycle.start) % cycle.period
+        };
+        history[index - offset].clone()
+    }
+}
+
+fn hash_grid<T: Hash>(grid: &Grid<T>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    grid.data.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::NEIGHBORS_8;
+
+    fn life(cell: &u8, neighbors: &[Option<&u8>]) -> u8 {
+        let alive = neighbors.iter().filter(|&&n| n == Some(&b'#')).count();
+        match (cell, alive) {
+            (b'#', 2 | 3) | (b'.', 3) => b'#',
+            _ => b'.',
+        }
+    }
+
+    #[test]
+    fn step_and_stabilize() {
+        let block = Grid::parse_grid_bytes("....\n.##.\n.#..\n....").unwrap();
+        let next = block.step(&NEIGHBORS_8, life);
+        assert_eq!(
+            next,
+            Grid::parse_grid_bytes("....\n.##.\n.##.\n....").unwrap()
+        );
+
+        let mut automaton = Automaton::new(block, &NEIGHBORS_8, life);
+        assert_eq!(automaton.run_until_stable(), 2);
+    }
+
+    #[test]
+    fn directional_neighborhood() {
+        // everything falls one row, taking what was above it; the top row has nothing above
+        // it, and must not get the cell below instead
+        let fall = |_: &u8, neighbors: &[Option<&u8>]| neighbors[0].copied().unwrap_or(b'.');
+        let grid = Grid::parse_grid_bytes("#.\n.#\n..").unwrap();
+        assert_eq!(
+            grid.step(&[(0, -1), (0, 1)], fall),
+            Grid::parse_grid_bytes("..\n#.\n.#").unwrap()
+        );
+    }
+
+    #[test]
+    fn extrapolate_cycle() {
+        let blinker = Grid::parse_grid_bytes(".....\n..#..\n..#..\n..#..\n.....").unwrap();
+        let mut automaton = Automaton::new(blinker.clone(), &NEIGHBORS_8, life);
+        automaton.step();
+        let flipped = automaton.grid().clone();
+
+        let (cycle, _) = Automaton::new(blinker.clone(), &NEIGHBORS_8, life).find_cycle();
+        assert_eq!(
+            cycle,
+            Cycle {
+                start: 0,
+                period: 2
+            }
+        );
+        assert_eq!(automaton.state_at(1_000_000_000), blinker);
+        assert_eq!((automaton.generation, automaton.grid()), (1, &flipped));
+
+        // asking again, even about an earlier generation, sees the same automaton
+        let mut automaton = Automaton::new(blinker.clone(), &NEIGHBORS_8, life);
+        assert_eq!(automaton.state_at(1_000_000_001), flipped);
+        assert_eq!(automaton.state_at(1_000_000_000), blinker);
+        assert_eq!(automaton.state_at(3), flipped);
+        assert_eq!((automaton.generation, automaton.grid()), (0, &blinker));
+    }
+}
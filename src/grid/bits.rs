@@ -0,0 +1,350 @@
+use super::Grid;
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+
+/// Boolean grid packed one bit per cell. Each row starts on a fresh `u64`, and bits past the
+/// right edge are always kept clear so popcounts and shifts never see garbage.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    words: Vec<u64>,
+    words_per_row: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
+        Self {
+            words: vec![0; words_per_row * height],
+            words_per_row,
+            width,
+            height,
+        }
+    }
+
+    pub fn from_grid<T>(grid: &Grid<T>, is_set: impl Fn(&T) -> bool) -> Self {
+        let mut bits = Self::new(grid.width, grid.height);
+        for (y, row) in grid.data.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if is_set(cell) {
+                    bits.set((x, y), true);
+                }
+            }
+        }
+        bits
+    }
+
+    /// Parses lines of text, setting every cell that holds `on`
+    pub fn parse(input: &str, on: u8) -> Result<Self, &'static str> {
+        let grid = Grid::parse_grid_bytes(input)?;
+        Ok(Self::from_grid(&grid, |&b| b == on))
+    }
+
+    pub fn to_grid(&self) -> Grid<bool> {
+        let data = (0..self.height)
+            .map(|y| (0..self.width).map(|x| self.get((x, y))).collect())
+            .collect();
+        Grid::new(data).unwrap()
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Mask of the bits in a row's last word that are inside the grid
+    fn tail_mask(&self) -> u64 {
+        match self.width % 64 {
+            0 => u64::MAX,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    fn clear_tails(&mut self) {
+        if self.words_per_row == 0 {
+            return;
+        }
+        let (mask, last) = (self.tail_mask(), self.words_per_row - 1);
+        for y in 0..self.height {
+            self.row_mut(y)[last] &= mask;
+        }
+    }
+
+    pub fn get(&self, (x, y): (usize, usize)) -> bool {
+        assert!(x < self.width && y < self.height, "Position out of bounds");
+        self.row(y)[x / 64] >> (x % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, (x, y): (usize, usize), value: bool) {
+        assert!(x < self.width && y < self.height, "Position out of bounds");
+        let word = &mut self.row_mut(y)[x / 64];
+        if value {
+            *word |= 1 << (x % 64);
+        } else {
+            *word &= !(1 << (x % 64));
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Positions of every set cell in row-major order
+    pub fn iter_ones(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height).flat_map(move |y| {
+            self.row(y).iter().enumerate().flat_map(move |(i, &word)| {
+                let mut word = word;
+                std::iter::from_fn(move || {
+                    (word != 0).then(|| {
+                        let bit = word.trailing_zeros() as usize;
+                        word &= word - 1;
+                        (i * 64 + bit, y)
+                    })
+                })
+            })
+        })
+    }
+
+    /// Moves every cell `dx` columns to the right (left if negative). Cells pushed off the edge
+    /// are dropped and the vacated columns are cleared.
+    pub fn shift_x(&self, dx: isize) -> Self {
+        let mut shifted = Self::new(self.width, self.height);
+        let (word_shift, bit_shift) = (dx.unsigned_abs() / 64, dx.unsigned_abs() % 64);
+        if word_shift >= self.words_per_row {
+            return shifted;
+        }
+        let n = self.words_per_row;
+        for y in 0..self.height {
+            let src = self.row(y);
+            let dst = shifted.row_mut(y);
+            for (i, word) in dst.iter_mut().enumerate() {
+                *word = if dx >= 0 {
+                    let Some(j) = i.checked_sub(word_shift) else {
+                        continue;
+                    };
+                    let carry = match (bit_shift, j.checked_sub(1)) {
+                        (0, _) | (_, None) => 0,
+                        (_, Some(k)) => src[k] >> (64 - bit_shift),
+                    };
+                    src[j] << bit_shift | carry
+                } else {
+                    let j = i + word_shift;
+                    if j >= n {
+                        continue;
+                    }
+                    let carry = match (bit_shift, src.get(j + 1)) {
+                        (0, _) | (_, None) => 0,
+                        (_, Some(&next)) => next << (64 - bit_shift),
+                    };
+                    src[j] >> bit_shift | carry
+                };
+            }
+        }
+        shifted.clear_tails();
+        shifted
+    }
+
+    /// Moves every row `dy` rows down (up if negative), clearing the vacated rows
+    pub fn shift_y(&self, dy: isize) -> Self {
+        let mut shifted = Self::new(self.width, self.height);
+        let offset = dy.unsigned_abs();
+        if offset >= self.height {
+            return shifted;
+        }
+        let span = (self.height - offset) * self.words_per_row;
+        let moved = offset * self.words_per_row;
+        if dy >= 0 {
+            shifted.words[moved..].copy_from_slice(&self.words[..span]);
+        } else {
+            shifted.words[..span].copy_from_slice(&self.words[moved..]);
+        }
+        shifted
+    }
+
+    /// Bit-sliced count of set neighbors (8-way) for every cell, as four bit planes: the count at
+    /// a cell is the sum of `planes[i].get(pos) << i`
+    pub fn neighbor_counts(&self) -> [BitGrid; 4] {
+        let mut planes = [
+            Self::new(self.width, self.height),
+            Self::new(self.width, self.height),
+            Self::new(self.width, self.height),
+            Self::new(self.width, self.height),
+        ];
+        let (left, right) = (self.shift_x(1), self.shift_x(-1));
+        let rows = [left.clone(), self.clone(), right.clone()];
+        let addends = rows
+            .iter()
+            .flat_map(|row| [row.shift_y(1), row.shift_y(-1)])
+            .chain([left, right]);
+
+        for addend in addends {
+            for (i, &word) in addend.words.iter().enumerate() {
+                // ripple-carry add `word` into the four planes, all 64 cells at once
+                let mut carry = word;
+                for plane in planes.iter_mut() {
+                    let sum = plane.words[i] ^ carry;
+                    carry &= plane.words[i];
+                    plane.words[i] = sum;
+                }
+            }
+        }
+        planes
+    }
+
+    /// Cells with exactly `n` set neighbors
+    pub fn neighbors_exactly(&self, n: u8) -> Self {
+        Self::count_equals(&self.neighbor_counts(), n)
+    }
+
+    fn count_equals(planes: &[BitGrid; 4], n: u8) -> Self {
+        // no cell has more than 8 neighbors, and the planes only hold the low 4 bits of `n`
+        if n > 8 {
+            return Self::new(planes[0].width, planes[0].height);
+        }
+        let mut mask = !Self::new(planes[0].width, planes[0].height);
+        for (i, plane) in planes.iter().enumerate() {
+            if n >> i & 1 == 1 {
+                mask &= plane;
+            } else {
+                mask &= &!plane;
+            }
+        }
+        mask
+    }
+
+    /// One generation of a life-like automaton: dead cells with a neighbor count in `birth` come
+    /// alive, live cells with a count in `survive` stay alive. Conway's game is `(&[3], &[2, 3])`.
+    pub fn life_step(&self, birth: &[u8], survive: &[u8]) -> Self {
+        let planes = self.neighbor_counts();
+        let mut next = Self::new(self.width, self.height);
+        for &n in birth {
+            next |= &(&Self::count_equals(&planes, n) & &!self);
+        }
+        for &n in survive {
+            next |= &(&Self::count_equals(&planes, n) & self);
+        }
+        next
+    }
+}
+
+impl Not for &BitGrid {
+    type Output = BitGrid;
+
+    fn not(self) -> BitGrid {
+        let mut inverted = self.clone();
+        inverted.words.iter_mut().for_each(|w| *w = !*w);
+        inverted.clear_tails();
+        inverted
+    }
+}
+
+impl Not for BitGrid {
+    type Output = BitGrid;
+
+    fn not(self) -> BitGrid {
+        !&self
+    }
+}
+
+macro_rules! bit_op {
+    ($op:ident, $method:ident, $assign:ident, $assign_method:ident, $token:tt) => {
+        impl $assign<&BitGrid> for BitGrid {
+            fn $assign_method(&mut self, other: &BitGrid) {
+                assert_eq!(
+                    (self.width, self.height),
+                    (other.width, other.height),
+                    "BitGrids must be the same size"
+                );
+                for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+                    *word $token *other_word;
+                }
+            }
+        }
+
+        impl $op<&BitGrid> for &BitGrid {
+            type Output = BitGrid;
+
+            fn $method(self, other: &BitGrid) -> BitGrid {
+                let mut result = self.clone();
+                result.$assign_method(other);
+                result
+            }
+        }
+    };
+}
+
+bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &=);
+bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |=);
+bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^=);
+
+impl fmt::Display for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                write!(f, "{}", if self.get((x, y)) { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shifts_cross_words() {
+        let mut bits = BitGrid::new(130, 2);
+        bits.set((63, 0), true);
+        bits.set((129, 1), true);
+        let east = bits.shift_x(1);
+        assert_eq!(east.iter_ones().collect::<Vec<_>>(), vec![(64, 0)]);
+        let west = bits.shift_x(-66);
+        assert_eq!(west.iter_ones().collect::<Vec<_>>(), vec![(63, 1)]);
+        let down = bits.shift_y(1);
+        assert_eq!(down.iter_ones().collect::<Vec<_>>(), vec![(63, 1)]);
+        assert!(bits.shift_y(-2).is_empty());
+    }
+
+    #[test]
+    fn boolean_ops() {
+        let a = BitGrid::parse("##.\n...", b'#').unwrap();
+        let b = BitGrid::parse(".##\n..#", b'#').unwrap();
+        assert_eq!((&a & &b).count_ones(), 1);
+        assert_eq!((&a | &b).count_ones(), 4);
+        assert_eq!((&a ^ &b).count_ones(), 3);
+        assert_eq!((!&a).count_ones(), 4);
+    }
+
+    #[test]
+    fn game_of_life() {
+        let blinker = BitGrid::parse(".....\n..#..\n..#..\n..#..\n.....", b'#').unwrap();
+        assert_eq!(blinker.neighbors_exactly(3).count_ones(), 2);
+        let flipped = blinker.life_step(&[3], &[2, 3]);
+        assert_eq!(flipped.to_string(), ".....\n.....\n.###.\n.....\n.....\n");
+        assert_eq!(flipped.life_step(&[3], &[2, 3]), blinker);
+        assert!(blinker.neighbors_exactly(16).is_empty());
+        assert!(blinker.neighbors_exactly(9).is_empty());
+        assert_eq!(blinker.life_step(&[16], &[16]).count_ones(), 0);
+
+        // a glider on a wide board keeps its five cells as it crosses word boundaries
+        let mut glider = BitGrid::new(100, 8);
+        for pos in [(61, 0), (62, 1), (60, 2), (61, 2), (62, 2)] {
+            glider.set(pos, true);
+        }
+        for _ in 0..12 {
+            glider = glider.life_step(&[3], &[2, 3]);
+            assert_eq!(glider.count_ones(), 5);
+        }
+        assert!(glider.get((65, 5)));
+    }
+}
@@ -0,0 +1,299 @@
+use super::search::Board;
+use super::Grid;
+use std::collections::VecDeque;
+use std::ops::{Index, IndexMut};
+
+/// A signed (x, y, z) position
+pub type Coord3 = (isize, isize, isize);
+
+/// (dx, dy, dz) offsets of the six face neighbors
+pub const NEIGHBORS_6: [Coord3; 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// Three dimensional grid stored flat in x, then y, then z order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid3<T> {
+    data: Vec<T>,
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+}
+
+impl<T> Grid3<T> {
+    pub fn in_bounds(&self, (x, y, z): Coord3) -> bool {
+        x >= 0
+            && y >= 0
+            && z >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && (z as usize) < self.depth
+    }
+
+    fn flat(&self, (x, y, z): Coord3) -> Option<usize> {
+        self.in_bounds((x, y, z))
+            .then(|| (z as usize * self.height + y as usize) * self.width + x as usize)
+    }
+
+    pub fn get(&self, pos: Coord3) -> Option<&T> {
+        self.flat(pos).map(|i| &self.data[i])
+    }
+
+    pub fn get_mut(&mut self, pos: Coord3) -> Option<&mut T> {
+        self.flat(pos).map(|i| &mut self.data[i])
+    }
+
+    /// Every position in storage order
+    pub fn positions(&self) -> impl Iterator<Item = Coord3> {
+        let (width, height, depth) = (
+            self.width as isize,
+            self.height as isize,
+            self.depth as isize,
+        );
+        (0..depth)
+            .flat_map(move |z| (0..height).flat_map(move |y| (0..width).map(move |x| (x, y, z))))
+    }
+
+    /// In-bounds positions sharing a face with `pos`
+    pub fn neighbors6(&self, (x, y, z): Coord3) -> impl Iterator<Item = Coord3> + '_ {
+        NEIGHBORS_6
+            .into_iter()
+            .map(move |(dx, dy, dz)| (x + dx, y + dy, z + dz))
+            .filter(|&next| self.in_bounds(next))
+    }
+
+    /// In-bounds positions sharing a face, edge or corner with `pos`
+    pub fn neighbors26(&self, (x, y, z): Coord3) -> impl Iterator<Item = Coord3> + '_ {
+        (-1..=1)
+            .flat_map(|dz| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (dx, dy, dz))))
+            .filter(|&delta| delta != (0, 0, 0))
+            .map(move |(dx, dy, dz)| (x + dx, y + dy, z + dz))
+            .filter(|&next| self.in_bounds(next))
+    }
+
+    /// Marks the open cells that can be reached from outside the grid without passing through a
+    /// solid cell. Open cells left unmarked are air pockets sealed inside.
+    pub fn exterior(&self, is_solid: impl Fn(&T) -> bool) -> Grid3<bool> {
+        let mut outside = Grid3::new(self.width, self.height, self.depth, false);
+        // search a box one cell larger on every side, so the outside is one connected region
+        let (pw, ph, pd) = (self.width + 2, self.height + 2, self.depth + 2);
+        let padded =
+            |(x, y, z): Coord3| ((z + 1) as usize * ph + (y + 1) as usize) * pw + (x + 1) as usize;
+        let in_padding = |(x, y, z): Coord3| {
+            x >= -1
+                && y >= -1
+                && z >= -1
+                && x <= self.width as isize
+                && y <= self.height as isize
+                && z <= self.depth as isize
+        };
+
+        let mut seen = vec![false; pw * ph * pd];
+        let start = (-1, -1, -1);
+        seen[padded(start)] = true;
+        let mut queue = VecDeque::from([start]);
+        while let Some((x, y, z)) = queue.pop_front() {
+            for (dx, dy, dz) in NEIGHBORS_6 {
+                let next = (x + dx, y + dy, z + dz);
+                if !in_padding(next) || seen[padded(next)] {
+                    continue;
+                }
+                if let Some(cell) = self.get(next) {
+                    if is_solid(cell) {
+                        continue;
+                    }
+                    outside[next] = true;
+                }
+                seen[padded(next)] = true;
+                queue.push_back(next);
+            }
+        }
+        outside
+    }
+
+    /// Counts the faces of solid cells that touch an open cell or the edge of the grid
+    pub fn surface_area(&self, is_solid: impl Fn(&T) -> bool) -> usize {
+        self.count_faces(&is_solid, |next| {
+            self.get(next).is_none_or(|cell| !is_solid(cell))
+        })
+    }
+
+    /// Like `surface_area`, but ignores faces that only touch sealed-in air pockets
+    pub fn exterior_surface_area(&self, is_solid: impl Fn(&T) -> bool) -> usize {
+        let outside = self.exterior(&is_solid);
+        self.count_faces(&is_solid, |next| outside.get(next).is_none_or(|&open| open))
+    }
+
+    fn count_faces(
+        &self,
+        is_solid: impl Fn(&T) -> bool,
+        exposed: impl Fn(Coord3) -> bool,
+    ) -> usize {
+        self.positions()
+            .filter(|&pos| is_solid(&self[pos]))
+            .map(|(x, y, z)| {
+                NEIGHBORS_6
+                    .iter()
+                    .filter(|(dx, dy, dz)| exposed((x + dx, y + dy, z + dz)))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+impl<T: Clone> Grid3<T> {
+    pub fn new(width: usize, height: usize, depth: usize, fill: T) -> Self {
+        Self {
+            data: vec![fill; width * height * depth],
+            width,
+            height,
+            depth,
+        }
+    }
+
+    /// Builds the smallest grid holding every point, with `filled` at the points and `empty`
+    /// elsewhere. Returns the grid along with the position its (0, 0, 0) corresponds to.
+    pub fn from_points(points: &[Coord3], filled: T, empty: T) -> (Self, Coord3) {
+        let Some(&first) = points.first() else {
+            return (Self::new(0, 0, 0, empty), (0, 0, 0));
+        };
+        let (mut min, mut max) = (first, first);
+        for &(x, y, z) in points {
+            min = (min.0.min(x), min.1.min(y), min.2.min(z));
+            max = (max.0.max(x), max.1.max(y), max.2.max(z));
+        }
+        let mut grid = Self::new(
+            (max.0 - min.0 + 1) as usize,
+            (max.1 - min.1 + 1) as usize,
+            (max.2 - min.2 + 1) as usize,
+            empty,
+        );
+        for &(x, y, z) in points {
+            grid[(x - min.0, y - min.1, z - min.2)] = filled.clone();
+        }
+        (grid, min)
+    }
+
+    /// Cuts out the plane at `index` along `axis`. Slicing along X gives a (y, z) grid, along Y
+    /// an (x, z) grid, and along Z an (x, y) grid.
+    pub fn slice(&self, axis: Axis, index: usize) -> Grid<T>
+    where
+        T: PartialEq,
+    {
+        let index = index as isize;
+        let (columns, rows) = match axis {
+            Axis::X => (self.height, self.depth),
+            Axis::Y => (self.width, self.depth),
+            Axis::Z => (self.width, self.height),
+        };
+        let data = (0..rows as isize)
+            .map(|row| {
+                (0..columns as isize)
+                    .map(|col| {
+                        let pos = match axis {
+                            Axis::X => (index, col, row),
+                            Axis::Y => (col, index, row),
+                            Axis::Z => (col, row, index),
+                        };
+                        self[pos].clone()
+                    })
+                    .collect()
+            })
+            .collect();
+        Grid::new(data).unwrap()
+    }
+}
+
+impl<T> Index<Coord3> for Grid3<T> {
+    type Output = T;
+
+    fn index(&self, pos: Coord3) -> &Self::Output {
+        self.get(pos).expect("Position out of bounds")
+    }
+}
+
+impl<T> IndexMut<Coord3> for Grid3<T> {
+    fn index_mut(&mut self, pos: Coord3) -> &mut Self::Output {
+        self.get_mut(pos).expect("Position out of bounds")
+    }
+}
+
+impl<T> Board for Grid3<T> {
+    type Cell = T;
+    type Pos = Coord3;
+
+    fn cell(&self, pos: Coord3) -> Option<&T> {
+        self.get(pos)
+    }
+
+    fn neighbors(&self, pos: Coord3) -> Vec<Coord3> {
+        self.neighbors6(pos).collect()
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Coord3> + '_ {
+        Grid3::positions(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // lava droplet from the AoC 2022 day 18 example
+    const DROPLET: [Coord3; 13] = [
+        (2, 2, 2),
+        (1, 2, 2),
+        (3, 2, 2),
+        (2, 1, 2),
+        (2, 3, 2),
+        (2, 2, 1),
+        (2, 2, 3),
+        (2, 2, 4),
+        (2, 2, 6),
+        (1, 2, 5),
+        (3, 2, 5),
+        (2, 1, 5),
+        (2, 3, 5),
+    ];
+
+    #[test]
+    fn droplet_surface() {
+        let (grid, origin) = Grid3::from_points(&DROPLET, true, false);
+        assert_eq!(origin, (1, 1, 1));
+        assert_eq!((grid.width, grid.height, grid.depth), (3, 3, 6));
+        assert_eq!(grid.surface_area(|&solid| solid), 64);
+        assert_eq!(grid.exterior_surface_area(|&solid| solid), 58);
+
+        let outside = grid.exterior(|&solid| solid);
+        assert!(!outside[(1, 1, 4)]); // the sealed pocket at (2, 2, 5)
+        assert!(outside[(0, 0, 0)]);
+    }
+
+    #[test]
+    fn neighbors_and_slices() {
+        let mut grid = Grid3::new(3, 3, 3, 0u8);
+        assert_eq!(grid.neighbors6((1, 1, 1)).count(), 6);
+        assert_eq!(grid.neighbors26((1, 1, 1)).count(), 26);
+        assert_eq!(grid.neighbors26((0, 0, 0)).count(), 7);
+
+        grid[(2, 0, 1)] = 5;
+        let z = grid.slice(Axis::Z, 1);
+        assert_eq!(z[(2, 0)], 5);
+        let y = grid.slice(Axis::Y, 0);
+        assert_eq!(y[(2, 1)], 5);
+        let x = grid.slice(Axis::X, 2);
+        assert_eq!(x[(0, 1)], 5);
+    }
+}
@@ -0,0 +1,302 @@
+use super::search::Board;
+use super::Grid;
+use std::ops::{Add, Mul, Sub};
+
+/// Axial hex coordinate. The third cube coordinate is implied by `q + r + s == 0`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Hex {
+    pub q: isize,
+    pub r: isize,
+}
+
+impl Hex {
+    /// The six unit steps, going counterclockwise from +q
+    pub const DIRECTIONS: [Hex; 6] = [
+        Hex { q: 1, r: 0 },
+        Hex { q: 1, r: -1 },
+        Hex { q: 0, r: -1 },
+        Hex { q: -1, r: 0 },
+        Hex { q: -1, r: 1 },
+        Hex { q: 0, r: 1 },
+    ];
+
+    pub fn new(q: isize, r: isize) -> Self {
+        Hex { q, r }
+    }
+
+    pub fn from_cube(q: isize, r: isize, s: isize) -> Self {
+        assert_eq!(q + r + s, 0, "Cube coordinates must sum to zero");
+        Hex { q, r }
+    }
+
+    pub fn s(&self) -> isize {
+        -self.q - self.r
+    }
+
+    pub fn neighbors(&self) -> [Hex; 6] {
+        Self::DIRECTIONS.map(|direction| *self + direction)
+    }
+
+    pub fn distance(&self, other: Hex) -> usize {
+        let d = *self - other;
+        (d.q.unsigned_abs() + d.r.unsigned_abs() + d.s().unsigned_abs()) / 2
+    }
+
+    /// Every hex exactly `radius` steps away, walking around the ring
+    pub fn ring(&self, radius: usize) -> Vec<Hex> {
+        if radius == 0 {
+            return vec![*self];
+        }
+        let mut hex = *self + Self::DIRECTIONS[4] * radius as isize;
+        let mut ring = Vec::with_capacity(6 * radius);
+        for direction in Self::DIRECTIONS {
+            for _ in 0..radius {
+                ring.push(hex);
+                hex = hex + direction;
+            }
+        }
+        ring
+    }
+
+    /// Hexes crossed by a straight line from `self` to `other`, both ends included
+    pub fn line(&self, other: Hex) -> Vec<Hex> {
+        let n = self.distance(other);
+        // nudge off the exact midpoint so ties between two hexes always break the same way
+        let (aq, ar, a_s) = (
+            self.q as f64 + 1e-6,
+            self.r as f64 + 1e-6,
+            self.s() as f64 - 2e-6,
+        );
+        let (bq, br, bs) = (
+            other.q as f64 + 1e-6,
+            other.r as f64 + 1e-6,
+            other.s() as f64 - 2e-6,
+        );
+        (0..=n)
+            .map(|i| {
+                let t = if n == 0 { 0.0 } else { i as f64 / n as f64 };
+                cube_round(aq + (bq - aq) * t, ar + (br - ar) * t, a_s + (bs - a_s) * t)
+            })
+            .collect()
+    }
+
+    /// Center of the hex in pixel space, for hexes with the given circumradius
+    pub fn to_pixel(&self, layout: HexLayout, size: f64) -> (f64, f64) {
+        let (q, r) = (self.q as f64, self.r as f64);
+        let sqrt3 = 3f64.sqrt();
+        match layout {
+            HexLayout::PointyTop => (size * (sqrt3 * q + sqrt3 / 2.0 * r), size * 1.5 * r),
+            HexLayout::FlatTop => (size * 1.5 * q, size * (sqrt3 / 2.0 * q + sqrt3 * r)),
+        }
+    }
+}
+
+fn cube_round(q: f64, r: f64, s: f64) -> Hex {
+    let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+    let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+    if dq > dr && dq > ds {
+        rq = -rr - rs;
+    } else if dr > ds {
+        rr = -rq - rs;
+    }
+    Hex::new(rq as isize, rr as isize)
+}
+
+impl Add for Hex {
+    type Output = Hex;
+
+    fn add(self, other: Hex) -> Hex {
+        Hex::new(self.q + other.q, self.r + other.r)
+    }
+}
+
+impl Sub for Hex {
+    type Output = Hex;
+
+    fn sub(self, other: Hex) -> Hex {
+        Hex::new(self.q - other.q, self.r - other.r)
+    }
+}
+
+impl Mul<isize> for Hex {
+    type Output = Hex;
+
+    fn mul(self, k: isize) -> Hex {
+        Hex::new(self.q * k, self.r * k)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexLayout {
+    PointyTop,
+    FlatTop,
+}
+
+/// How a hex board is laid out as rows of text. The `R` variants are pointy-top boards where
+/// every odd (or even) row is shoved half a hex to the right; the `Q` variants are flat-top boards
+/// where every odd (or even) column is shoved half a hex down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexOffset {
+    OddR,
+    EvenR,
+    OddQ,
+    EvenQ,
+}
+
+impl HexOffset {
+    pub fn layout(&self) -> HexLayout {
+        match self {
+            HexOffset::OddR | HexOffset::EvenR => HexLayout::PointyTop,
+            HexOffset::OddQ | HexOffset::EvenQ => HexLayout::FlatTop,
+        }
+    }
+
+    pub fn to_hex(&self, (col, row): (usize, usize)) -> Hex {
+        let (col, row) = (col as isize, row as isize);
+        match self {
+            HexOffset::OddR => Hex::new(col - (row - (row & 1)) / 2, row),
+            HexOffset::EvenR => Hex::new(col - (row + (row & 1)) / 2, row),
+            HexOffset::OddQ => Hex::new(col, row - (col - (col & 1)) / 2),
+            HexOffset::EvenQ => Hex::new(col, row - (col + (col & 1)) / 2),
+        }
+    }
+
+    /// The (col, row) text position of a hex, which may be off the board or negative
+    pub fn to_offset(&self, hex: Hex) -> (isize, isize) {
+        let Hex { q, r } = hex;
+        match self {
+            HexOffset::OddR => (q + (r - (r & 1)) / 2, r),
+            HexOffset::EvenR => (q + (r + (r & 1)) / 2, r),
+            HexOffset::OddQ => (q, r + (q - (q & 1)) / 2),
+            HexOffset::EvenQ => (q, r + (q + (q & 1)) / 2),
+        }
+    }
+}
+
+/// A bounded hex board, stored as its offset-coordinate rows but addressed by `Hex`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexGrid<T> {
+    pub grid: Grid<T>,
+    pub offset: HexOffset,
+}
+
+impl<T> HexGrid<T> {
+    pub fn new(grid: Grid<T>, offset: HexOffset) -> Self {
+        Self { grid, offset }
+    }
+
+    pub fn layout(&self) -> HexLayout {
+        self.offset.layout()
+    }
+
+    fn grid_pos(&self, hex: Hex) -> Option<(usize, usize)> {
+        let (col, row) = self.offset.to_offset(hex);
+        (col >= 0
+            && row >= 0
+            && (col as usize) < self.grid.width
+            && (row as usize) < self.grid.height)
+            .then_some((col as usize, row as usize))
+    }
+
+    pub fn contains(&self, hex: Hex) -> bool {
+        self.grid_pos(hex).is_some()
+    }
+
+    pub fn get(&self, hex: Hex) -> Option<&T> {
+        self.grid_pos(hex).map(|pos| &self.grid[pos])
+    }
+
+    pub fn get_mut(&mut self, hex: Hex) -> Option<&mut T> {
+        self.grid_pos(hex).map(|pos| &mut self.grid[pos])
+    }
+
+    /// Neighbors of `hex` that are on the board
+    pub fn neighbors(&self, hex: Hex) -> impl Iterator<Item = Hex> + '_ {
+        hex.neighbors()
+            .into_iter()
+            .filter(|&neighbor| self.contains(neighbor))
+    }
+}
+
+impl HexGrid<u8> {
+    /// Parses rows of text into a hex board. Whitespace is ignored, so both packed rows and the
+    /// usual staggered, space-separated drawings work.
+    pub fn parse_bytes(input: &str, offset: HexOffset) -> Result<Self, &'static str> {
+        let data: Vec<Vec<u8>> = input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.bytes().filter(|b| !b.is_ascii_whitespace()).collect())
+            .collect();
+        Ok(Self::new(Grid::new(data)?, offset))
+    }
+}
+
+impl<T> Board for HexGrid<T> {
+    type Cell = T;
+    type Pos = Hex;
+
+    fn cell(&self, pos: Hex) -> Option<&T> {
+        self.get(pos)
+    }
+
+    fn neighbors(&self, pos: Hex) -> Vec<Hex> {
+        HexGrid::neighbors(self, pos).collect()
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Hex> + '_ {
+        self.grid.positions().map(|pos| self.offset.to_hex(pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::search::{bfs, find_all};
+
+    #[test]
+    fn distance_ring_line() {
+        let origin = Hex::new(0, 0);
+        assert_eq!(origin.distance(Hex::from_cube(3, -1, -2)), 3);
+        assert_eq!(origin.ring(0), vec![origin]);
+        let ring = origin.ring(2);
+        assert_eq!(ring.len(), 12);
+        assert!(ring.iter().all(|hex| hex.distance(origin) == 2));
+
+        let line = origin.line(Hex::new(3, -3));
+        assert_eq!(line, (0..=3).map(|i| Hex::new(i, -i)).collect::<Vec<_>>());
+        let line = Hex::new(-2, 0).line(Hex::new(2, 1));
+        assert_eq!(line.len(), 6);
+        assert!(line.windows(2).all(|w| w[0].distance(w[1]) == 1));
+    }
+
+    #[test]
+    fn offset_round_trip() {
+        for offset in [
+            HexOffset::OddR,
+            HexOffset::EvenR,
+            HexOffset::OddQ,
+            HexOffset::EvenQ,
+        ] {
+            for row in 0..5 {
+                for col in 0..5 {
+                    let hex = offset.to_hex((col, row));
+                    assert_eq!(offset.to_offset(hex), (col as isize, row as isize));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_and_search() {
+        // pointy-top, odd rows shoved right
+        let board = HexGrid::parse_bytes("a . .\n . # .\n. . b", HexOffset::OddR).unwrap();
+        assert_eq!(board.layout(), HexLayout::PointyTop);
+        let a = find_all(&board, |&c| c == b'a')[0];
+        let b = find_all(&board, |&c| c == b'b')[0];
+        assert_eq!(a.distance(b), 3);
+        assert_eq!(board.neighbors(Hex::new(0, 0)).count(), 2);
+
+        let distances = bfs(&board, a, |_, &to| to != b'#');
+        assert_eq!(distances[&b], 3);
+    }
+}
@@ -0,0 +1,131 @@
+use super::render::topmost_overlays;
+use super::{Grid, Overlay};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+impl<T> Grid<T> {
+    /// Writes the grid as a binary PPM image, with every cell drawn as a `scale` x `scale` block
+    /// colored by `to_rgb`. Overlays are painted on top in their own color, later ones winning.
+    pub fn write_ppm(
+        &self,
+        path: impl AsRef<Path>,
+        scale: usize,
+        to_rgb: impl Fn(&T) -> [u8; 3],
+        overlays: &[Overlay],
+    ) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.encode_ppm(&mut out, scale, to_rgb, overlays)?;
+        out.flush()
+    }
+
+    /// Writes the grid as a binary PGM (grayscale) image. Overlays are painted using the
+    /// brightness of their color.
+    pub fn write_pgm(
+        &self,
+        path: impl AsRef<Path>,
+        scale: usize,
+        to_gray: impl Fn(&T) -> u8,
+        overlays: &[Overlay],
+    ) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.encode_pgm(&mut out, scale, to_gray, overlays)?;
+        out.flush()
+    }
+
+    pub fn encode_ppm(
+        &self,
+        out: &mut impl Write,
+        scale: usize,
+        to_rgb: impl Fn(&T) -> [u8; 3],
+        overlays: &[Overlay],
+    ) -> io::Result<()> {
+        self.encode_netpbm(out, "P6", scale, overlays, |cell, overlay| {
+            overlay.map_or_else(|| to_rgb(cell), |overlay| overlay.color.rgb())
+        })
+    }
+
+    pub fn encode_pgm(
+        &self,
+        out: &mut impl Write,
+        scale: usize,
+        to_gray: impl Fn(&T) -> u8,
+        overlays: &[Overlay],
+    ) -> io::Result<()> {
+        self.encode_netpbm(out, "P5", scale, overlays, |cell, overlay| {
+            [overlay.map_or_else(|| to_gray(cell), |overlay| overlay.color.luma())]
+        })
+    }
+
+    /// Shared by both formats, which differ only in the header and in `N`, the bytes per pixel.
+    /// Rows are built once into a reused buffer and written `scale` times.
+    fn encode_netpbm<const N: usize>(
+        &self,
+        out: &mut impl Write,
+        magic: &str,
+        scale: usize,
+        overlays: &[Overlay],
+        pixel: impl Fn(&T, Option<&Overlay>) -> [u8; N],
+    ) -> io::Result<()> {
+        if scale == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Scale must be at least 1",
+            ));
+        }
+
+        let on_top = topmost_overlays(overlays, self.width, self.height);
+        write!(
+            out,
+            "{}\n{} {}\n255\n",
+            magic,
+            self.width * scale,
+            self.height * scale
+        )?;
+        let mut line = Vec::with_capacity(self.width * scale * N);
+        for (y, row) in self.data.iter().enumerate() {
+            line.clear();
+            for (x, cell) in row.iter().enumerate() {
+                let color = pixel(cell, on_top[y * self.width + x]);
+                for _ in 0..scale {
+                    line.extend_from_slice(&color);
+                }
+            }
+            for _ in 0..scale {
+                out.write_all(&line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Color;
+
+    #[test]
+    fn ppm_with_overlay() {
+        let grid = Grid::<u8>::parse_grid_nums("09\n90").unwrap();
+        let overlay = Overlay::new([(1, 1)], Color::Red);
+        let mut out = Vec::new();
+        grid.encode_ppm(&mut out, 1, |&h| [h * 20; 3], &[overlay])
+            .unwrap();
+
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend_from_slice(&[0, 0, 0, 180, 180, 180, 180, 180, 180, 255, 0, 0]);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn pgm_scaled() {
+        let grid = Grid::<u8>::parse_grid_nums("19").unwrap();
+        let mut out = Vec::new();
+        grid.encode_pgm(&mut out, 2, |&h| h, &[]).unwrap();
+
+        let mut expected = b"P5\n4 2\n255\n".to_vec();
+        expected.extend_from_slice(&[1, 1, 9, 9, 1, 1, 9, 9]);
+        assert_eq!(out, expected);
+        assert!(grid.encode_pgm(&mut Vec::new(), 0, |&h| h, &[]).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use super::{gcd, Grid};
+
+impl<T> Grid<T> {
+    /// Positions reached by repeatedly stepping `delta` from `from` (not including `from`
+    /// itself), stopping at the edge of the grid
+    pub fn ray(
+        &self,
+        from: (usize, usize),
+        delta: (isize, isize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut pos = (from.0 as isize, from.1 as isize);
+        std::iter::from_fn(move || {
+            if delta == (0, 0) {
+                return None;
+            }
+            pos = (pos.0 + delta.0, pos.1 + delta.1);
+            self.is_valid_boint(pos)
+                .then_some((pos.0 as usize, pos.1 as usize))
+        })
+    }
+
+    /// Every lattice point on the infinite line through `a` and `b` that lies inside the grid,
+    /// in order from one edge to the other. The step is reduced by the gcd of the offsets, so
+    /// points between `a` and `b` are included too.
+    pub fn line(&self, a: (usize, usize), b: (usize, usize)) -> Vec<(usize, usize)> {
+        let (dx, dy) = (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize);
+        if (dx, dy) == (0, 0) {
+            return vec![a];
+        }
+        let divisor = gcd(dx.unsigned_abs(), dy.unsigned_abs()) as isize;
+        let step = (dx / divisor, dy / divisor);
+
+        let mut points: Vec<_> = self.ray(a, (-step.0, -step.1)).collect();
+        points.reverse();
+        points.push(a);
+        points.extend(self.ray(a, step));
+        points
+    }
+
+    /// The first position along `ray(from, delta)` whose cell matches `pred`, if one comes
+    /// before the edge
+    pub fn find_along(
+        &self,
+        from: (usize, usize),
+        delta: (isize, isize),
+        pred: impl Fn((usize, usize), &T) -> bool,
+    ) -> Option<(usize, usize)> {
+        self.ray(from, delta).find(|&pos| pred(pos, &self[pos]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rays_and_lines() {
+        let grid = Grid::<u8>::parse_grid_bytes(".....\n.....\n..#..\n.....\n.....").unwrap();
+        assert_eq!(
+            grid.ray((1, 1), (1, 1)).collect::<Vec<_>>(),
+            vec![(2, 2), (3, 3), (4, 4)]
+        );
+        assert_eq!(grid.ray((0, 0), (-1, 0)).count(), 0);
+        assert_eq!(grid.ray((0, 0), (0, 0)).count(), 0);
+
+        assert_eq!(grid.line((1, 0), (3, 4)), vec![(1, 0), (2, 2), (3, 4)]);
+        assert_eq!(
+            grid.line((4, 4), (2, 2)),
+            vec![(4, 4), (3, 3), (2, 2), (1, 1), (0, 0)]
+        );
+
+        assert_eq!(
+            grid.find_along((2, 0), (0, 1), |_, &cell| cell == b'#'),
+            Some((2, 2))
+        );
+        assert_eq!(
+            grid.find_along((0, 2), (0, 1), |_, &cell| cell == b'#'),
+            None
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use super::{Grid, VisitSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    fn ansi_code(&self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+        }
+    }
+
+    pub fn rgb(&self) -> [u8; 3] {
+        match self {
+            Color::Red => [255, 0, 0],
+            Color::Green => [0, 200, 0],
+            Color::Yellow => [255, 220, 0],
+            Color::Blue => [0, 80, 255],
+            Color::Magenta => [220, 0, 220],
+            Color::Cyan => [0, 220, 220],
+            Color::White => [255, 255, 255],
+        }
+    }
+
+    /// Perceived brightness, for drawing overlays in grayscale
+    pub fn luma(&self) -> u8 {
+        let [r, g, b] = self.rgb();
+        ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+    }
+}
+
+/// A set of positions to highlight on top of a grid, e.g. a path, a visited set, or antinodes.
+/// Highlighted cells keep their own character unless a glyph is given.
+#[derive(Clone, Debug)]
+pub struct Overlay {
+    pub positions: Vec<(usize, usize)>,
+    pub color: Color,
+    pub glyph: Option<char>,
+}
+
+impl Overlay {
+    pub fn new(positions: impl IntoIterator<Item = (usize, usize)>, color: Color) -> Self {
+        Self {
+            positions: positions.into_iter().collect(),
+            color,
+            glyph: None,
+        }
+    }
+
+    pub fn from_visits(visits: &VisitSet, color: Color) -> Self {
+        Self::new(visits.iter(), color)
+    }
+
+    pub fn with_glyph(mut self, glyph: char) -> Self {
+        self.glyph = Some(glyph);
+        self
+    }
+}
+
+/// For every cell in row-major order, the last overlay that covers it
+pub(super) fn topmost_overlays(
+    overlays: &[Overlay],
+    width: usize,
+    height: usize,
+) -> Vec<Option<&Overlay>> {
+    let mut on_top = vec![None; width * height];
+    for overlay in overlays {
+        for &(x, y) in &overlay.positions {
+            if x < width && y < height {
+                on_top[y * width + x] = Some(overlay);
+            }
+        }
+    }
+    on_top
+}
+
+/// Draws a grid as text, one character per cell, with optional ANSI-colored overlays. Later
+/// overlays are drawn over earlier ones.
+pub struct Renderer<F> {
+    to_char: F,
+    overlays: Vec<Overlay>,
+    ansi: bool,
+}
+
+impl<F> Renderer<F> {
+    pub fn new(to_char: F) -> Self {
+        Self {
+            to_char,
+            overlays: Vec::new(),
+            ansi: true,
+        }
+    }
+
+    pub fn overlay(mut self, overlay: Overlay) -> Self {
+        self.overlays.push(overlay);
+        self
+    }
+
+    /// With ANSI off, overlays only show up through their glyphs, which is what you want in a file
+    pub fn ansi(mut self, enabled: bool) -> Self {
+        self.ansi = enabled;
+        self
+    }
+
+    pub fn set_overlays(&mut self, overlays: Vec<Overlay>) {
+        self.overlays = overlays;
+    }
+
+    pub fn render<T>(&self, grid: &Grid<T>) -> String
+    where
+        F: Fn(&T) -> char,
+    {
+        let on_top = topmost_overlays(&self.overlays, grid.width, grid.height);
+        let mut out = String::with_capacity((grid.width + 1) * grid.height);
+        for (y, row) in grid.data.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let ch = (self.to_char)(cell);
+                match on_top[y * grid.width + x] {
+                    Some(overlay) => {
+                        let ch = overlay.glyph.unwrap_or(ch);
+                        if self.ansi {
+                            out.push_str(&format!(
+                                "\x1b[1;{}m{}\x1b[0m",
+                                overlay.color.ansi_code(),
+                                ch
+                            ));
+                        } else {
+                            out.push(ch);
+                        }
+                    }
+                    None => out.push(ch),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+enum Playback {
+    Terminal(Duration),
+    File(BufWriter<File>),
+}
+
+/// Replays a simulation one frame at a time, either redrawing the terminal with a delay between
+/// frames or appending every frame to a file.
+pub struct Animation {
+    playback: Playback,
+    every: usize,
+    steps: usize,
+    frames: usize,
+}
+
+impl Animation {
+    pub fn terminal(delay: Duration) -> Self {
+        Self::with_playback(Playback::Terminal(delay))
+    }
+
+    pub fn to_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::with_playback(Playback::File(BufWriter::new(file))))
+    }
+
+    fn with_playback(playback: Playback) -> Self {
+        Self {
+            playback,
+            every: 1,
+            steps: 0,
+            frames: 0,
+        }
+    }
+
+    /// Only draw every `n`th step, to speed through long simulations
+    pub fn every(mut self, n: usize) -> Self {
+        self.every = n.max(1);
+        self
+    }
+
+    /// Advances the simulation by one step. `render` is only called for steps that get drawn.
+    pub fn step(&mut self, render: impl FnOnce() -> String) -> io::Result<()> {
+        let draw = self.steps.is_multiple_of(self.every);
+        self.steps += 1;
+        if draw {
+            self.frame(&render())?;
+        }
+        Ok(())
+    }
+
+    pub fn frame(&mut self, frame: &str) -> io::Result<()> {
+        match &mut self.playback {
+            Playback::Terminal(delay) => {
+                let mut stdout = io::stdout().lock();
+                // move the cursor home and clear the screen before drawing
+                write!(stdout, "\x1b[H\x1b[2J{}", frame)?;
+                stdout.flush()?;
+                thread::sleep(*delay);
+            }
+            Playback::File(writer) => {
+                writeln!(writer, "frame {}", self.frames)?;
+                writeln!(writer, "{}", frame)?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Flushes any buffered output and returns the number of frames drawn
+    pub fn finish(self) -> io::Result<usize> {
+        if let Playback::File(mut writer) = self.playback {
+            writer.flush()?;
+        }
+        Ok(self.frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_with_overlay() {
+        let grid = Grid::<u8>::parse_grid_bytes("#..\n.#.\n..#").unwrap();
+        let plain = Renderer::new(|&b: &u8| b as char).ansi(false);
+        assert_eq!(plain.render(&grid), "#..\n.#.\n..#\n");
+
+        let path = plain.overlay(Overlay::new([(1, 0), (2, 1)], Color::Red).with_glyph('X'));
+        assert_eq!(path.render(&grid), "#X.\n.#X\n..#\n");
+
+        let colored =
+            Renderer::new(|&b: &u8| b as char).overlay(Overlay::new([(0, 0)], Color::Green));
+        assert!(colored.render(&grid).starts_with("\x1b[1;32m#\x1b[0m..\n"));
+    }
+}
@@ -0,0 +1,102 @@
+use super::Grid;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+/// Read access shared by the different grid shapes, so searches only have to be written once.
+/// `neighbors` should only return positions that `cell` knows about.
+pub trait Board {
+    type Cell;
+    type Pos: Copy + Eq + Hash;
+
+    fn cell(&self, pos: Self::Pos) -> Option<&Self::Cell>;
+    fn neighbors(&self, pos: Self::Pos) -> Vec<Self::Pos>;
+    fn positions(&self) -> impl Iterator<Item = Self::Pos> + '_;
+}
+
+impl<T> Board for Grid<T> {
+    type Cell = T;
+    type Pos = (usize, usize);
+
+    fn cell(&self, (x, y): Self::Pos) -> Option<&T> {
+        self.data.get(y).and_then(|row| row.get(x))
+    }
+
+    fn neighbors(&self, pos: Self::Pos) -> Vec<Self::Pos> {
+        self.neighbors4(pos).collect()
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Self::Pos> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+    }
+}
+
+/// Every position whose cell matches `pred`, in the board's own order
+pub fn find_all<B: Board>(board: &B, pred: impl Fn(&B::Cell) -> bool) -> Vec<B::Pos> {
+    board
+        .positions()
+        .filter(|&pos| board.cell(pos).is_some_and(&pred))
+        .collect()
+}
+
+/// Breadth-first search from `start`, only stepping from one cell to the next when
+/// `can_step(from, to)` allows it. Returns the step count to every reachable position.
+pub fn bfs<B: Board>(
+    board: &B,
+    start: B::Pos,
+    can_step: impl Fn(&B::Cell, &B::Cell) -> bool,
+) -> HashMap<B::Pos, usize> {
+    let mut distances = HashMap::new();
+    if board.cell(start).is_none() {
+        return distances;
+    }
+    distances.insert(start, 0);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(pos) = queue.pop_front() {
+        let (here, distance) = (&board.cell(pos).unwrap(), distances[&pos]);
+        for next in board.neighbors(pos) {
+            if distances.contains_key(&next) {
+                continue;
+            }
+            if let Some(there) = board.cell(next) {
+                if can_step(here, there) {
+                    distances.insert(next, distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    distances
+}
+
+/// All positions connected to `start` through cells matching `pred`
+pub fn flood_fill<B: Board>(
+    board: &B,
+    start: B::Pos,
+    pred: impl Fn(&B::Cell) -> bool,
+) -> HashSet<B::Pos> {
+    if !board.cell(start).is_some_and(&pred) {
+        return HashSet::new();
+    }
+    bfs(board, start, |_, to| pred(to)).into_keys().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_dense_grid() {
+        let grid = Grid::<u8>::parse_grid_bytes("..#\n.##\n...").unwrap();
+        assert_eq!(
+            find_all(&grid, |&b| b == b'#'),
+            vec![(2, 0), (1, 1), (2, 1)]
+        );
+
+        let distances = bfs(&grid, (0, 0), |_, &to| to == b'.');
+        assert_eq!(distances[&(2, 2)], 4);
+        assert!(!distances.contains_key(&(2, 0)));
+
+        assert_eq!(flood_fill(&grid, (2, 0), |&b| b == b'#').len(), 3);
+    }
+}
@@ -0,0 +1,245 @@
+use super::search::Board;
+use super::{Grid, Renderer, NEIGHBORS_4, NEIGHBORS_8};
+use std::collections::HashMap;
+
+/// A signed (x, y) position on an unbounded plane
+pub type ICoord = (isize, isize);
+
+/// Grid over the whole signed plane, for puzzles that grow past any fixed size. Only cells that
+/// have been written are stored; everything else reads as `default`. The bounding box grows to
+/// cover every write and never shrinks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<ICoord, T>,
+    default: T,
+    bounds: Option<(ICoord, ICoord)>,
+}
+
+impl<T: Clone + PartialEq> SparseGrid<T> {
+    pub fn new(default: T) -> Self {
+        Self {
+            cells: HashMap::new(),
+            default,
+            bounds: None,
+        }
+    }
+
+    /// Copies a dense grid onto the plane with its top-left corner at `origin`. Cells equal to
+    /// `default` are not stored, but still count towards the bounds.
+    pub fn from_grid(grid: &Grid<T>, origin: ICoord, default: T) -> Self {
+        let mut sparse = Self::new(default);
+        if grid.width == 0 || grid.height == 0 {
+            return sparse;
+        }
+        for (y, row) in grid.data.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if *cell != sparse.default {
+                    let pos = (origin.0 + x as isize, origin.1 + y as isize);
+                    sparse.cells.insert(pos, cell.clone());
+                }
+            }
+        }
+        sparse.bounds = Some((
+            origin,
+            (
+                origin.0 + grid.width as isize - 1,
+                origin.1 + grid.height as isize - 1,
+            ),
+        ));
+        sparse
+    }
+
+    /// Copies the bounding box into a dense grid, returning it along with the plane position of
+    /// its top-left corner
+    pub fn to_grid(&self) -> (Grid<T>, ICoord) {
+        let Some((min, max)) = self.bounds else {
+            return (Grid::new(Vec::new()).unwrap(), (0, 0));
+        };
+        let data = (min.1..=max.1)
+            .map(|y| (min.0..=max.0).map(|x| self.get((x, y)).clone()).collect())
+            .collect();
+        (Grid::new(data).unwrap(), min)
+    }
+
+    pub fn get(&self, pos: ICoord) -> &T {
+        self.cells.get(&pos).unwrap_or(&self.default)
+    }
+
+    pub fn set(&mut self, pos: ICoord, value: T) {
+        self.grow_to(pos);
+        if value == self.default {
+            self.cells.remove(&pos);
+        } else {
+            self.cells.insert(pos, value);
+        }
+    }
+
+    fn grow_to(&mut self, (x, y): ICoord) {
+        self.bounds = Some(match self.bounds {
+            None => ((x, y), (x, y)),
+            Some((min, max)) => ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y))),
+        });
+    }
+
+    /// Inclusive (min, max) corners of everything written so far
+    pub fn bounds(&self) -> Option<(ICoord, ICoord)> {
+        self.bounds
+    }
+
+    pub fn in_bounds(&self, (x, y): ICoord) -> bool {
+        self.bounds
+            .is_some_and(|(min, max)| x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1)
+    }
+
+    pub fn width(&self) -> usize {
+        self.bounds
+            .map_or(0, |(min, max)| (max.0 - min.0 + 1) as usize)
+    }
+
+    pub fn height(&self) -> usize {
+        self.bounds
+            .map_or(0, |(min, max)| (max.1 - min.1 + 1) as usize)
+    }
+
+    /// Number of cells holding something other than the default
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Iterates over the stored (non-default) cells in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (ICoord, &T)> {
+        self.cells.iter().map(|(&pos, value)| (pos, value))
+    }
+
+    /// The first position holding `value` in row-major order. Searching for the default looks
+    /// through the bounding box, since every unwritten cell in it reads as the default.
+    pub fn find_first(&self, value: &T) -> Option<ICoord> {
+        if *value == self.default {
+            return self.positions().find(|pos| !self.cells.contains_key(pos));
+        }
+        self.cells
+            .iter()
+            .filter(|(_, cell)| *cell == value)
+            .map(|(&pos, _)| pos)
+            .min_by_key(|&(x, y)| (y, x))
+    }
+
+    /// Every position holding `value`, in row-major order. As with `find_first`, the default
+    /// is found everywhere in the bounding box that hasn't been written.
+    pub fn find_all(&self, value: &T) -> Vec<ICoord> {
+        if *value == self.default {
+            return self
+                .positions()
+                .filter(|pos| !self.cells.contains_key(pos))
+                .collect();
+        }
+        let mut locs: Vec<ICoord> = self
+            .cells
+            .iter()
+            .filter(|(_, cell)| *cell == value)
+            .map(|(&pos, _)| pos)
+            .collect();
+        locs.sort_by_key(|&(x, y)| (y, x));
+        locs
+    }
+
+    pub fn neighbors4(&self, (x, y): ICoord) -> [ICoord; 4] {
+        NEIGHBORS_4.map(|(dx, dy)| (x + dx, y + dy))
+    }
+
+    pub fn neighbors8(&self, (x, y): ICoord) -> [ICoord; 8] {
+        NEIGHBORS_8.map(|(dx, dy)| (x + dx, y + dy))
+    }
+
+    /// Converts a plane position into the matching cell of `to_grid`, e.g. for overlays
+    pub fn to_dense_pos(&self, (x, y): ICoord) -> Option<(usize, usize)> {
+        let (min, _) = self.bounds?;
+        self.in_bounds((x, y))
+            .then(|| ((x - min.0) as usize, (y - min.1) as usize))
+    }
+
+    /// Renders the bounding box. Overlay positions are in `to_dense_pos` coordinates.
+    pub fn render<F: Fn(&T) -> char>(&self, renderer: &Renderer<F>) -> String {
+        renderer.render(&self.to_grid().0)
+    }
+}
+
+/// Searches on a sparse grid are confined to its bounding box, since the plane itself never ends
+impl<T: Clone + PartialEq> Board for SparseGrid<T> {
+    type Cell = T;
+    type Pos = ICoord;
+
+    fn cell(&self, pos: ICoord) -> Option<&T> {
+        self.in_bounds(pos).then(|| self.get(pos))
+    }
+
+    fn neighbors(&self, pos: ICoord) -> Vec<ICoord> {
+        self.neighbors4(pos)
+            .into_iter()
+            .filter(|&next| self.in_bounds(next))
+            .collect()
+    }
+
+    fn positions(&self) -> impl Iterator<Item = ICoord> + '_ {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds.unwrap_or(((0, 0), (-1, -1)));
+        (min_y..=max_y).flat_map(move |y| (min_x..=max_x).map(move |x| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::search::bfs;
+
+    #[test]
+    fn grows_and_round_trips() {
+        let mut sparse = SparseGrid::new(b'.');
+        assert_eq!(sparse.bounds(), None);
+        sparse.set((-2, 1), b'#');
+        sparse.set((3, -1), b'o');
+        assert_eq!(sparse.bounds(), Some(((-2, -1), (3, 1))));
+        assert_eq!((sparse.width(), sparse.height()), (6, 3));
+        assert_eq!(*sparse.get((100, 100)), b'.');
+        assert_eq!(sparse.len(), 2);
+
+        let (dense, origin) = sparse.to_grid();
+        assert_eq!(origin, (-2, -1));
+        assert_eq!(
+            dense,
+            Grid::parse_grid_bytes(".....o\n......\n#.....").unwrap()
+        );
+        assert_eq!(sparse.to_dense_pos((3, -1)), Some((5, 0)));
+
+        let back = SparseGrid::from_grid(&dense, origin, b'.');
+        assert_eq!(back, sparse);
+        assert_eq!(back.find_all(&b'#'), vec![(-2, 1)]);
+        assert_eq!(back.find_first(&b'o'), Some((3, -1)));
+        assert_eq!(back.find_first(&b'x'), None);
+    }
+
+    #[test]
+    fn find_default() {
+        let mut sparse = SparseGrid::new(b'.');
+        assert_eq!(sparse.find_first(&b'.'), None);
+        sparse.set((0, 0), b'#');
+        sparse.set((1, 1), b'#');
+        assert_eq!(sparse.find_all(&b'.'), vec![(1, 0), (0, 1)]);
+        assert_eq!(sparse.find_first(&b'.'), Some((1, 0)));
+        // writing the default still grows the box, and the cell reads as the default
+        sparse.set((2, 1), b'.');
+        assert_eq!(sparse.find_all(&b'.'), vec![(1, 0), (2, 0), (0, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn search_inside_bounds() {
+        let dense = Grid::parse_grid_bytes("...\n.#.\n...").unwrap();
+        let sparse = SparseGrid::from_grid(&dense, (-1, -1), b'.');
+        let distances = bfs(&sparse, (-1, -1), |_, &to| to == b'.');
+        assert_eq!(distances.len(), 8);
+        assert_eq!(distances[&(1, 1)], 4);
+    }
+}
@@ -0,0 +1,177 @@
+use super::{gcd, Grid, ICoord, NEIGHBORS_4, NEIGHBORS_8};
+
+/// The shape of a board whose edges wrap around, so every signed coordinate lands on some cell.
+/// Only the dimensions are needed, so entities can be moved around without a grid at all.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Torus {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Torus {
+    /// Panics if either side is zero, since there'd be nothing to wrap onto
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "A torus needs at least one cell");
+        Self { width, height }
+    }
+
+    /// Reduces signed coordinates modulo the width and height
+    pub fn wrap(&self, (x, y): ICoord) -> (usize, usize) {
+        (
+            x.rem_euclid(self.width as isize) as usize,
+            y.rem_euclid(self.height as isize) as usize,
+        )
+    }
+
+    /// Where an entity at `pos` moving by `velocity` each step ends up after `n` steps, in
+    /// constant time and without overflowing for huge `n`
+    pub fn step(&self, pos: ICoord, velocity: ICoord, n: u64) -> (usize, usize) {
+        let axis = |start: isize, speed: isize, size: usize| {
+            let size = size as u128;
+            let start = start.rem_euclid(size as isize) as u128;
+            let speed = speed.rem_euclid(size as isize) as u128;
+            ((start + speed * (n as u128 % size)) % size) as usize
+        };
+        (
+            axis(pos.0, velocity.0, self.width),
+            axis(pos.1, velocity.1, self.height),
+        )
+    }
+
+    /// Moves every `(position, velocity)` entity forward by `n` steps
+    pub fn step_all(&self, entities: &mut [((usize, usize), ICoord)], n: u64) {
+        for (pos, velocity) in entities.iter_mut() {
+            *pos = self.step((pos.0 as isize, pos.1 as isize), *velocity, n);
+        }
+    }
+
+    pub fn neighbors4(&self, (x, y): (usize, usize)) -> [(usize, usize); 4] {
+        NEIGHBORS_4.map(|(dx, dy)| self.wrap((x as isize + dx, y as isize + dy)))
+    }
+
+    pub fn neighbors8(&self, (x, y): (usize, usize)) -> [(usize, usize); 8] {
+        NEIGHBORS_8.map(|(dx, dy)| self.wrap((x as isize + dx, y as isize + dy)))
+    }
+
+    /// Positions reached by repeatedly stepping `delta` from `from`, wrapping at the edges and
+    /// stopping just before the ray comes back around to `from`
+    pub fn ray(&self, from: (usize, usize), delta: ICoord) -> TorusRay {
+        let period = |d: isize, size: usize| size / gcd(d.rem_euclid(size as isize) as usize, size);
+        let cycle = lcm(period(delta.0, self.width), period(delta.1, self.height));
+        TorusRay {
+            torus: *self,
+            pos: from,
+            delta,
+            remaining: cycle - 1,
+        }
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+pub struct TorusRay {
+    torus: Torus,
+    pos: (usize, usize),
+    delta: ICoord,
+    remaining: usize,
+}
+
+impl Iterator for TorusRay {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.pos = self.torus.wrap((
+            self.pos.0 as isize + self.delta.0,
+            self.pos.1 as isize + self.delta.1,
+        ));
+        Some(self.pos)
+    }
+}
+
+impl<T> Grid<T> {
+    /// The wrapping view of this grid's shape, or `None` for an empty grid, which has no cell
+    /// for anything to wrap onto
+    pub fn torus(&self) -> Option<Torus> {
+        (self.width > 0 && self.height > 0).then(|| Torus::new(self.width, self.height))
+    }
+
+    /// The cell at `pos` with the edges wrapped. Panics on an empty grid.
+    pub fn get_wrapped(&self, pos: ICoord) -> &T {
+        &self[self.wrapped(pos)]
+    }
+
+    /// The cell at `pos` with the edges wrapped. Panics on an empty grid.
+    pub fn get_wrapped_mut(&mut self, pos: ICoord) -> &mut T {
+        let pos = self.wrapped(pos);
+        &mut self[pos]
+    }
+
+    fn wrapped(&self, pos: ICoord) -> (usize, usize) {
+        self.torus()
+            .expect("An empty grid has no cells to wrap onto")
+            .wrap(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_and_steps() {
+        let torus = Torus::new(11, 7);
+        assert_eq!(torus.wrap((-1, -8)), (10, 6));
+        // robot from the AoC 2024 day 14 example
+        let mut robot = (2, 4);
+        for _ in 0..5 {
+            robot = torus.step((robot.0 as isize, robot.1 as isize), (2, -3), 1);
+        }
+        assert_eq!(robot, (1, 3));
+        assert_eq!(torus.step((2, 4), (2, -3), 5), (1, 3));
+        assert_eq!(
+            torus.step((2, 4), (2, -3), 1_000_000_000_007),
+            torus.step((2, 4), (2, -3), 1_000_000_000_007 % 77)
+        );
+
+        let mut entities = [((0, 0), (-1, 1)), ((5, 5), (3, 3))];
+        torus.step_all(&mut entities, 2);
+        assert_eq!(entities[0].0, (9, 2));
+        assert_eq!(entities[1].0, (0, 4));
+    }
+
+    #[test]
+    fn neighbors_and_rays() {
+        let grid = Grid::<u8>::parse_grid_nums("123\n456").unwrap();
+        let torus = grid.torus().unwrap();
+        assert_eq!(torus.neighbors4((0, 0)), [(0, 1), (0, 1), (2, 0), (1, 0)]);
+        assert_eq!(*grid.get_wrapped((-1, -1)), 6);
+
+        let ray: Vec<_> = torus.ray((0, 0), (1, 0)).collect();
+        assert_eq!(ray, vec![(1, 0), (2, 0)]);
+        let diagonal: Vec<_> = torus.ray((0, 0), (1, 1)).collect();
+        assert_eq!(diagonal, vec![(1, 1), (2, 0), (0, 1), (1, 0), (2, 1)]);
+        assert_eq!(torus.ray((1, 1), (3, -2)).count(), 0);
+    }
+
+    #[test]
+    fn empty_grid() {
+        let empty = Grid::<u8>::new(Vec::new()).unwrap();
+        assert_eq!(empty.torus(), None);
+        assert_eq!(
+            Grid::new(vec![vec![1u8]]).unwrap().torus(),
+            Some(Torus::new(1, 1))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "A torus needs at least one cell")]
+    fn zero_width_torus() {
+        Torus::new(0, 3);
+    }
+}
@@ -0,0 +1,146 @@
+use super::Direction;
+
+const STATES_PER_CELL: usize = 4;
+const CELLS_PER_WORD: usize = 64 / STATES_PER_CELL;
+// lowest bit of every 4-bit cell in a word
+const CELL_MASK: u64 = 0x1111_1111_1111_1111;
+
+/// Packed record of which (position, direction) states have been seen on a grid. Every cell gets
+/// four bits, one per `Direction`, so a 130x130 board fits in about 8KB and the grid itself never
+/// has to be cloned or mutated to remember where something has been.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisitSet {
+    words: Vec<u64>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl VisitSet {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words = (width * height).div_ceil(CELLS_PER_WORD);
+        Self {
+            words: vec![0; words],
+            width,
+            height,
+        }
+    }
+
+    fn bit(&self, (x, y): (usize, usize), direction: Direction) -> (usize, u64) {
+        debug_assert!(x < self.width && y < self.height);
+        let index = (y * self.width + x) * STATES_PER_CELL + direction as usize;
+        (index / 64, 1 << (index % 64))
+    }
+
+    fn cell_bits(&self, (x, y): (usize, usize)) -> u64 {
+        let cell = y * self.width + x;
+        let shift = (cell % CELLS_PER_WORD) * STATES_PER_CELL;
+        (self.words[cell / CELLS_PER_WORD] >> shift) & 0b1111
+    }
+
+    /// Records the state, returning `true` if it had not been seen before (like `HashSet::insert`)
+    pub fn insert(&mut self, position: (usize, usize), direction: Direction) -> bool {
+        let (word, mask) = self.bit(position, direction);
+        let is_new = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        is_new
+    }
+
+    pub fn contains(&self, position: (usize, usize), direction: Direction) -> bool {
+        let (word, mask) = self.bit(position, direction);
+        self.words[word] & mask != 0
+    }
+
+    /// Whether the cell has been visited facing any direction
+    pub fn contains_cell(&self, position: (usize, usize)) -> bool {
+        self.cell_bits(position) != 0
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    /// Adds every state from `other`, which must cover a grid of the same size
+    pub fn union_with(&mut self, other: &VisitSet) {
+        assert_eq!(
+            (self.width, self.height),
+            (other.width, other.height),
+            "VisitSets must cover grids of the same size"
+        );
+        for (word, other_word) in self.words.iter_mut().zip(&other.words) {
+            *word |= other_word;
+        }
+    }
+
+    /// Number of distinct cells visited, regardless of direction
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|&word| {
+                // fold each cell's four bits down onto its lowest bit
+                let folded = word | (word >> 1) | (word >> 2) | (word >> 3);
+                (folded & CELL_MASK).count_ones() as usize
+            })
+            .sum()
+    }
+
+    /// Number of distinct (position, direction) states visited
+    pub fn count_states(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Iterates over the visited cells in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&position| self.contains_cell(position))
+    }
+
+    /// Iterates over the directions a cell has been visited in
+    pub fn directions_at(&self, position: (usize, usize)) -> impl Iterator<Item = Direction> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter(move |&direction| self.contains(position, direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_count() {
+        let mut visits = VisitSet::new(5, 3);
+        assert!(visits.insert((4, 2), Direction::Left));
+        assert!(!visits.insert((4, 2), Direction::Left));
+        assert!(visits.insert((4, 2), Direction::Up));
+        assert!(visits.insert((0, 0), Direction::Down));
+        assert_eq!(visits.count(), 2);
+        assert_eq!(visits.count_states(), 3);
+        assert!(visits.contains((4, 2), Direction::Up));
+        assert!(!visits.contains((4, 2), Direction::Right));
+        assert_eq!(visits.iter().collect::<Vec<_>>(), vec![(0, 0), (4, 2)]);
+        assert_eq!(
+            visits.directions_at((4, 2)).collect::<Vec<_>>(),
+            vec![Direction::Up, Direction::Left]
+        );
+
+        visits.clear();
+        assert_eq!(visits.count(), 0);
+        assert_eq!(visits.iter().next(), None);
+    }
+
+    #[test]
+    fn union() {
+        let mut a = VisitSet::new(20, 20);
+        let mut b = VisitSet::new(20, 20);
+        a.insert((3, 7), Direction::Right);
+        b.insert((3, 7), Direction::Down);
+        b.insert((19, 19), Direction::Left);
+        a.union_with(&b);
+        assert_eq!(a.count(), 2);
+        assert_eq!(a.count_states(), 3);
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A position in the input: the text still to be read and how many bytes came before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor<'a> {
+    pub rest: &'a str,
+    pub offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Cursor {
+            rest: input,
+            offset: 0,
+        }
+    }
+
+    /// The cursor `len` bytes further on, with the skipped text
+    fn advance(self, len: usize) -> (&'a str, Self) {
+        let (taken, rest) = self.rest.split_at(len);
+        let next = Cursor {
+            rest,
+            offset: self.offset + len,
+        };
+        (taken, next)
+    }
+}
+
+/// Something that reads a `T` from the front of the input, returning it along with the cursor
+/// just past it, or `None` if the input doesn't start with one. Any
+/// `Fn(Cursor) -> Option<(T, Cursor)>` is a parser.
+pub trait Parser<'a, T> {
+    fn parse(&self, input: Cursor<'a>) -> Option<(T, Cursor<'a>)>;
+
+    fn map<U>(self, f: impl Fn(T) -> U) -> impl Parser<'a, U>
+    where
+        Self: Sized,
+    {
+        move |input| self.parse(input).map(|(value, rest)| (f(value), rest))
+    }
+
+    /// This, then `next`, keeping both
+    fn then<U>(self, next: impl Parser<'a, U>) -> impl Parser<'a, (T, U)>
+    where
+        Self: Sized,
+    {
+        seq(self, next)
+    }
+
+    /// This, then `next`, keeping only this
+    fn skip<U>(self, next: impl Parser<'a, U>) -> impl Parser<'a, T>
+    where
+        Self: Sized,
+    {
+        seq(self, next).map(|(value, _)| value)
+    }
+}
+
+impl<'a, T, F> Parser<'a, T> for F
+where
+    F: Fn(Cursor<'a>) -> Option<(T, Cursor<'a>)>,
+{
+    fn parse(&self, input: Cursor<'a>) -> Option<(T, Cursor<'a>)> {
+        self(input)
+    }
+}
+
+/// Exactly `expected`
+pub fn literal<'a>(expected: &'static str) -> impl Parser<'a, &'a str> {
+    move |input: Cursor<'a>| {
+        input
+            .rest
+            .starts_with(expected)
+            .then(|| input.advance(expected.len()))
+    }
+}
+
+/// The longest run of up to `max` characters matching `pred`, failing if that's fewer than `min`
+pub fn take_while<'a>(
+    min: usize,
+    max: usize,
+    pred: impl Fn(char) -> bool,
+) -> impl Parser<'a, &'a str> {
+    move |input: Cursor<'a>| {
+        let mut chars = 0;
+        let len = input
+            .rest
+            .char_indices()
+            .take(max)
+            .take_while(|&(_, c)| pred(c))
+            .inspect(|_| chars += 1)
+            .last()
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        (chars >= min).then(|| input.advance(len))
+    }
+}
+
+/// Between `min` and `max` ASCII digits. A longer run of digits still matches its first
+/// `max`, so follow this with something that can't be a digit to reject it outright.
+pub fn digits<'a>(min: usize, max: usize) -> impl Parser<'a, &'a str> {
+    take_while(min, max, |c| c.is_ascii_digit())
+}
+
+/// `digits(min, max)` parsed as a number. No signs or spaces are allowed.
+pub fn number<'a, N: FromStr>(min: usize, max: usize) -> impl Parser<'a, N> {
+    move |input| {
+        let (text, rest) = digits(min, max).parse(input)?;
+        Some((text.parse().ok()?, rest))
+    }
+}
+
+/// `first` followed by `second`
+pub fn seq<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, (A, B)> {
+    move |input| {
+        let (a, input) = first.parse(input)?;
+        let (b, input) = second.parse(input)?;
+        Some(((a, b), input))
+    }
+}
+
+/// `prefix` followed by `parser`, keeping only what `parser` reads
+pub fn preceded<'a, P, T>(
+    prefix: impl Parser<'a, P>,
+    parser: impl Parser<'a, T>,
+) -> impl Parser<'a, T> {
+    seq(prefix, parser).map(|(_, value)| value)
+}
+
+/// `first` if it matches, otherwise `second`
+pub fn alt<'a, T>(first: impl Parser<'a, T>, second: impl Parser<'a, T>) -> impl Parser<'a, T> {
+    move |input| first.parse(input).or_else(|| second.parse(input))
+}
+
+/// As many `parser` matches in a row as there are, possibly none
+pub fn many<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Vec<T>> {
+    move |mut input: Cursor<'a>| {
+        let mut values = Vec::new();
+        while let Some((value, next)) = parser.parse(input) {
+            values.push(value);
+            // a match that reads nothing would match forever
+            if next.offset == input.offset {
+                break;
+            }
+            input = next;
+        }
+        Some((values, input))
+    }
+}
+
+/// A parsed value along with where it was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spanned<T> {
+    pub value: T,
+    /// Byte offset of the first byte read
+    pub offset: usize,
+    /// Number of bytes read
+    pub len: usize,
+}
+
+/// `parser`, recording the bytes it read
+pub fn spanned<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, Spanned<T>> {
+    move |input: Cursor<'a>| {
+        let (value, rest) = parser.parse(input)?;
+        let spanned = Spanned {
+            value,
+            offset: input.offset,
+            len: rest.offset - input.offset,
+        };
+        Some((spanned, rest))
+    }
+}
+
+/// Every match of `parser` in `input`, searching from each character in turn and resuming
+/// after each match. Made for picking instructions out of noise.
+pub fn scan<'a, P, T>(parser: P, input: &'a str) -> impl Iterator<Item = Spanned<T>> + use<'a, P, T>
+where
+    P: Parser<'a, T>,
+{
+    let parser = spanned(parser);
+    let mut cursor = Cursor::new(input);
+    std::iter::from_fn(move || {
+        while !cursor.rest.is_empty() {
+            if let Some((found, next)) = parser.parse(cursor) {
+                if found.len > 0 {
+                    cursor = next;
+                    return Some(found);
+                }
+            }
+            let skip = cursor.rest.chars().next().map_or(0, char::len_utf8);
+            cursor = cursor.advance(skip).1;
+        }
+        None
+    })
+}
+
+/// Where a parse that had to read the whole input stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unexpected input at byte {}", self.offset)
+    }
+}
+
+/// Runs `parser` over all of `input`, failing if it doesn't match or leaves anything unread
+pub fn parse_all<'a, T>(parser: impl Parser<'a, T>, input: &'a str) -> Result<T, ParseError> {
+    match parser.parse(Cursor::new(input)) {
+        Some((value, rest)) if rest.rest.is_empty() => Ok(value),
+        Some((_, rest)) => Err(ParseError {
+            offset: rest.offset,
+        }),
+        None => Err(ParseError { offset: 0 }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn building_blocks() {
+        let input = Cursor::new("mul(12,345)");
+        let (text, rest) = literal("mul(").parse(input).unwrap();
+        assert_eq!((text, rest.offset), ("mul(", 4));
+        assert!(literal("do").parse(input).is_none());
+
+        let (n, rest) = number::<u32>(1, 3).parse(rest).unwrap();
+        assert_eq!((n, rest.rest), (12, ",345)"));
+        assert!(digits(1, 3).parse(Cursor::new("x1")).is_none());
+        assert_eq!(digits(1, 3).parse(Cursor::new("12345")).unwrap().0, "123");
+
+        let pair = number::<u32>(1, 3)
+            .skip(literal(","))
+            .then(number::<u32>(1, 3));
+        assert_eq!(
+            parse_all(preceded(literal("("), pair), "(1,22"),
+            Ok((1, 22))
+        );
+        assert_eq!(
+            parse_all(number::<u32>(1, 3), "12)"),
+            Err(ParseError { offset: 2 })
+        );
+        assert_eq!(
+            parse_all(number::<u32>(1, 3), " 12"),
+            Err(ParseError { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn repetition_and_choice() {
+        let ab = many(alt(literal("a"), literal("b")));
+        let (matched, rest) = ab.parse(Cursor::new("abbac")).unwrap();
+        assert_eq!(matched, vec!["a", "b", "b", "a"]);
+        assert_eq!(rest.rest, "c");
+        assert_eq!(ab.parse(Cursor::new("c")).unwrap().0.len(), 0);
+
+        let words = many(take_while(1, usize::MAX, char::is_alphabetic).skip(many(literal(" "))));
+        assert_eq!(parse_all(words, "héllo  wörld"), Ok(vec!["héllo", "wörld"]));
+    }
+
+    #[test]
+    fn scanning() {
+        let call = preceded(literal("f("), number::<u32>(1, 3)).skip(literal(")"));
+        let found: Vec<_> = scan(call, "xf(1)f(f(22)f(1234)é-f(3)").collect();
+        let values: Vec<_> = found.iter().map(|s| (s.value, s.offset)).collect();
+        assert_eq!(values, vec![(1, 1), (22, 7), (3, 22)]);
+        assert_eq!(found[1].len, 5);
+    }
+}